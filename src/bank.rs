@@ -1,21 +1,32 @@
 use std::fmt;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use crate::common::*;
 
-#[derive(Debug)]
+/// The pieces that aren't in play, available to be built, traded for or discovered.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Bank {
-    available_amounts: HashMap<Piece, u8>,
+    available_amounts: BTreeMap<Piece, u8>,
+    stock: u8,
 }
 
 impl Bank {
-    pub fn full() -> Bank {
-        let mut available_amounts = HashMap::new();
+    pub fn full(stock: u8) -> Bank {
+        Bank::with_each(stock, stock)
+    }
+
+    /// A bank with room for the stock of each piece, holding none of them.
+    pub fn empty(stock: u8) -> Bank {
+        Bank::with_each(0, stock)
+    }
+
+    fn with_each(amount: u8, stock: u8) -> Bank {
+        let mut available_amounts = BTreeMap::new();
         for color in ALL_COLORS.iter() {
             for size in ALL_SIZES.iter() {
-                available_amounts.insert(Piece {color: *color, size: *size}, 3);
+                available_amounts.insert(Piece {color: *color, size: *size}, amount);
             }
         }
-        Bank { available_amounts, }
+        Bank { available_amounts, stock }
     }
 
     pub fn num_available(&self, piece: Piece) -> u8 {
@@ -24,14 +35,33 @@ impl Bank {
 
     pub fn remove(&mut self, piece: Piece) -> Result<(), InputError> {
         if self.num_available(piece) < 1 {
-            return Err(InputError::PieceUnavailable);
+            return Err(InputError::PieceUnavailable { piece, available: 0 });
         }
         *self.available_amounts.get_mut(&piece).unwrap() -= 1;
         Ok(())
     }
 
     pub fn remove_several(&mut self, pieces: &[Piece]) -> Result<(), InputError> {
-        let mut requested_counts = HashMap::new();
+        self.check_several(pieces)?;
+        for (piece, count) in Bank::count_pieces(pieces).iter() {
+            *self.available_amounts.get_mut(piece).unwrap() -= count;
+        }
+        Ok(())
+    }
+
+    /// Whether all of the pieces could be removed at once.
+    pub fn check_several(&self, pieces: &[Piece]) -> Result<(), InputError> {
+        for (piece, count) in Bank::count_pieces(pieces).iter() {
+            let available = self.num_available(*piece);
+            if available < *count {
+                return Err(InputError::PieceUnavailable { piece: *piece, available });
+            }
+        }
+        Ok(())
+    }
+
+    fn count_pieces(pieces: &[Piece]) -> BTreeMap<Piece, u8> {
+        let mut requested_counts = BTreeMap::new();
         for piece in pieces.iter() {
             let count = requested_counts.get_mut(piece);
             match count {
@@ -43,20 +73,12 @@ impl Bank {
                 },
             }
         }
-        for (piece, count) in requested_counts.iter() {
-            if self.num_available(*piece) < *count {
-                return Err(InputError::PieceUnavailable);
-            }
-        }
-        for (piece, count) in requested_counts.iter() {
-            *self.available_amounts.get_mut(piece).unwrap() -= count;
-        }
-        Ok(())
+        requested_counts
     }
 
     pub fn add(&mut self, piece: Piece) -> Result<(), InputError> {
-        if self.num_available(piece) >= 3 {
-            return Err(InputError::BadPiece);
+        if self.num_available(piece) >= self.stock {
+            return Err(InputError::BadPiece(piece));
         }
         *self.available_amounts.get_mut(&piece).unwrap() += 1;
         Ok(())
@@ -76,7 +98,7 @@ impl fmt::Display for Bank {
                     write!(f, " ")?;
                 }
             }
-            if (color_index < ALL_COLORS.len() - 1) {
+            if color_index < ALL_COLORS.len() - 1 {
                 write!(f, " | ")?;
             }
         }
@@ -1,7 +1,9 @@
 use std::fmt;
 use std::cmp::Ordering;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// The names mirror the game's vocabulary rather than Rust casing.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Color {
     RED,
     BLUE,
@@ -23,7 +25,8 @@ impl fmt::Display for Color {
 
 pub const ALL_COLORS: [Color; 4] = [Color::RED, Color::BLUE, Color::GREEN, Color::YELLOW];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Size {
     SMALL,
     MEDIUM,
@@ -42,7 +45,7 @@ impl fmt::Display for Size {
 }
 
 impl Size {
-    pub fn to_u8(&self) -> u8 {
+    pub fn to_u8(self) -> u8 {
         match self {
             Size::SMALL => 1,
             Size::MEDIUM => 2,
@@ -53,16 +56,23 @@ impl Size {
 
 impl PartialOrd<Size> for Size {
     fn partial_cmp(&self, other: &Size) -> Option<Ordering> {
-        Some(self.to_u8().cmp(&other.to_u8()))
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Size {
+    fn cmp(&self, other: &Size) -> Ordering {
+        self.to_u8().cmp(&other.to_u8())
     }
 }
 
 pub const ALL_SIZES: [Size; 3] = [Size::SMALL, Size::MEDIUM, Size::LARGE];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Ordered by size, then color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Piece {
-    pub color: Color,
     pub size: Size,
+    pub color: Color,
 }
 
 impl fmt::Display for Piece {
@@ -71,28 +81,120 @@ impl fmt::Display for Piece {
     }
 }
 
+pub fn all_pieces() -> impl Iterator<Item = Piece> {
+    ALL_COLORS.iter().flat_map(|&color| ALL_SIZES.iter().map(move |&size| Piece { color, size }))
+}
+
 pub type PlayerIndex = u8;
-pub type SystemIndex = u8;
+/// Allocated once when a system comes into being and never reused, so it stays valid
+/// while other systems are discovered or evaporate.
+pub type SystemId = u32;
 
-pub const NUM_PLAYERS: u8 = 2;
+pub const MIN_PLAYERS: u8 = 2;
+pub const MAX_PLAYERS: u8 = 4;
 
-pub const CATASTROPHE_COUNT: i32 = 4;
+/// What happens when a turn ends with every remaining player's homeworld lost at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MutualDestruction {
+    Draw,
+    MoverLoses, // The player whose turn it was loses, everyone else wins.
+}
 
+/// Why an input was rejected, with whatever the player needs to see what went wrong.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InputError {
     WrongState,
     WrongPhase,
-    WrongPlayer,
-    PieceUnavailable,
-    WrongActionColor,
-    WrongSystem,
-    NoSuchShip,
-    BadSystem,
-    FreeActionUnavailable,
+    WrongPlayer { enemy_player: PlayerIndex, target: PlayerIndex }, // Only the target may be attacked
+    PieceUnavailable { piece: Piece, available: u8 },
+    ColorUnavailable(Color),
+    WrongActionColor { expected: Color, actual: Color },
+    WrongSystem { expected: SystemId, actual: SystemId },
+    NoSuchShip { system: SystemId, player: PlayerIndex, ship: Piece },
+    BadSystem(SystemId),
+    FreeActionUnavailable { system: SystemId, player: PlayerIndex, color: Color },
     NoActionsLeft,
-    WrongColor,
-    ShipTooBig,
-    SystemsNotAdjacent,
-    BadPiece,
-    NotCatastrophicEnough,
+    WrongColor(Color), // Trading a ship for its own color
+    ShipTooBig { ship: Piece, ship_to_take: Piece },
+    SystemsNotAdjacent { from: SystemId, to: SystemId },
+    StarNotAdjacent { from: SystemId, star: Piece },
+    BadPiece(Piece), // The bank would hold more of the piece than there are
+    NotCatastrophicEnough { system: SystemId, color: Color, count: u8, needed: u8 },
+    SetupNotAllowed(SetupRule),
+    TurnOver, // An input of a turn after the one that ended it
+    TurnUnfinished, // A turn that stops before it's ended
+}
+
+/// The optional restrictions on setup moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetupRule {
+    LargeStartingShip,
+    DistinctStarSizes,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            InputError::WrongState => write!(f, "That isn't possible at this stage of the game"),
+            InputError::WrongPhase => write!(f, "That isn't possible at this point in the turn"),
+            InputError::WrongPlayer { enemy_player, target } =>
+                write!(f, "Only player {}'s ships can be attacked this turn, not player {}'s", target, enemy_player),
+            InputError::PieceUnavailable { piece, available: 0 } => write!(f, "The bank has no {} left", piece),
+            InputError::PieceUnavailable { piece, available } => write!(f, "The bank has only {} {} left", available, piece),
+            InputError::ColorUnavailable(color) => write!(f, "The bank has no {} pieces left", color),
+            InputError::WrongActionColor { expected, actual } =>
+                write!(f, "Only {} actions are allowed now, not {}", expected, actual),
+            InputError::WrongSystem { expected, actual } =>
+                write!(f, "The free action has to be taken in system {}, not system {}", expected, actual),
+            InputError::NoSuchShip { system, player, ship } =>
+                write!(f, "Player {} has no {} ship in system {}", player, ship, system),
+            InputError::BadSystem(system) => write!(f, "There is no system {}", system),
+            InputError::FreeActionUnavailable { system, player, color } =>
+                write!(f, "Player {} has no {} star or ship of their own to use in system {}", player, color, system),
+            InputError::NoActionsLeft => write!(f, "There are no sacrifice actions left"),
+            InputError::WrongColor(color) => write!(f, "The ship is already {}", color),
+            InputError::ShipTooBig { ship, ship_to_take } =>
+                write!(f, "A {} ship can't capture a bigger {} ship", ship, ship_to_take),
+            InputError::SystemsNotAdjacent { from, to } =>
+                write!(f, "System {} isn't adjacent to system {}", to, from),
+            InputError::StarNotAdjacent { from, star } =>
+                write!(f, "A system with a {} star wouldn't be adjacent to system {}", star, from),
+            InputError::BadPiece(piece) => write!(f, "The bank already holds every {} there is", piece),
+            InputError::NotCatastrophicEnough { system, color, count, needed } =>
+                write!(f, "System {} has {} {} pieces, but a catastrophe needs {}", system, count, color, needed),
+            InputError::SetupNotAllowed(rule) => write!(f, "{}", rule),
+            InputError::TurnOver => write!(f, "The turn is already over"),
+            InputError::TurnUnfinished => write!(f, "The turn has to be ended, not left unfinished"),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// Why a turn was rejected: which of its inputs failed, counting from 0, and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TurnError {
+    pub index: usize,
+    pub error: InputError,
+}
+
+impl fmt::Display for TurnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Input {} of the turn: {}", self.index + 1, self.error)
+    }
+}
+
+impl std::error::Error for TurnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for SetupRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupRule::LargeStartingShip => write!(f, "The starting ship has to be large"),
+            SetupRule::DistinctStarSizes => write!(f, "The homeworld's stars have to be different sizes"),
+        }
+    }
 }
@@ -0,0 +1,118 @@
+use std::fmt;
+use crate::common::*;
+
+/// Something that happened to the board or to the players as the result of an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    SystemDiscovered { system: SystemId, star: Piece },
+    ShipMoved { player: PlayerIndex, ship: Piece, from: SystemId, to: SystemId },
+    ShipCaptured { system: SystemId, player: PlayerIndex, enemy_player: PlayerIndex, ship: Piece },
+    ShipTraded { system: SystemId, player: PlayerIndex, old_ship: Piece, new_ship: Piece },
+    ShipBuilt { system: SystemId, player: PlayerIndex, ship: Piece },
+    ShipSacrificed { system: SystemId, player: PlayerIndex, ship: Piece },
+    ShipDestroyed { system: SystemId, player: PlayerIndex, ship: Piece },
+    StarDestroyed { system: SystemId, star: Piece },
+    SystemEvaporated(SystemId),
+    PlayerEliminated(PlayerIndex),
+    GameWon(PlayerIndex),
+    GameDrawn,
+}
+
+impl GameEvent {
+    /// Describes the event, calling each player whatever `name` gives for them.
+    pub fn describe(&self, name: impl Fn(PlayerIndex) -> String) -> String {
+        match *self {
+            GameEvent::SystemDiscovered { system, star } =>
+                format!("System {} discovered with a {} star", system, star),
+            GameEvent::ShipMoved { player, ship, from, to } =>
+                format!("{}'s {} moved from system {} to system {}", name(player), ship, from, to),
+            GameEvent::ShipCaptured { system, player, enemy_player, ship } =>
+                format!("{} captured {}'s {} in system {}", name(player), name(enemy_player), ship, system),
+            GameEvent::ShipTraded { system, player, old_ship, new_ship } =>
+                format!("{} traded a {} for a {} in system {}", name(player), old_ship, new_ship, system),
+            GameEvent::ShipBuilt { system, player, ship } =>
+                format!("{} built a {} in system {}", name(player), ship, system),
+            GameEvent::ShipSacrificed { system, player, ship } =>
+                format!("{} sacrificed a {} in system {}", name(player), ship, system),
+            GameEvent::ShipDestroyed { system, player, ship } =>
+                format!("{}'s {} was destroyed in system {}", name(player), ship, system),
+            GameEvent::StarDestroyed { system, star } =>
+                format!("The {} star of system {} was destroyed", star, system),
+            GameEvent::SystemEvaporated(system) => format!("System {} evaporated", system),
+            GameEvent::PlayerEliminated(player) => format!("{} was eliminated", name(player)),
+            GameEvent::GameWon(player) => format!("{} won the game", name(player)),
+            GameEvent::GameDrawn => "The game ended in a draw".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(|player| format!("Player {}", player)))
+    }
+}
+
+/// Listens for events as they happen. Any `FnMut(&GameEvent)` closure is an observer.
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+impl<F: FnMut(&GameEvent)> GameObserver for F {
+    fn on_event(&mut self, event: &GameEvent) {
+        self(event)
+    }
+}
+
+pub type ObserverId = u32;
+
+/// The observers registered on one game. They belong to that game alone: a cloned game starts
+/// out with none, and they don't take part in comparing games.
+#[derive(Default)]
+pub(crate) struct Observers {
+    observers: Vec<(ObserverId, Box<dyn GameObserver>)>,
+    next_id: ObserverId,
+}
+
+impl Observers {
+    pub fn add(&mut self, observer: Box<dyn GameObserver>) -> ObserverId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    pub fn remove(&mut self, id: ObserverId) -> bool {
+        let position = self.observers.iter().position(|(observer_id, _)| *observer_id == id);
+        match position {
+            None => false,
+            Some(position) => {
+                self.observers.remove(position);
+                true
+            },
+        }
+    }
+
+    pub fn notify(&mut self, event: &GameEvent) {
+        for (_, observer) in self.observers.iter_mut() {
+            observer.on_event(event);
+        }
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Observers {
+        Observers::default()
+    }
+}
+
+impl PartialEq for Observers {
+    fn eq(&self, _other: &Observers) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observer(s)", self.observers.len())
+    }
+}
@@ -1,48 +1,556 @@
 use std::fmt;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use crate::common::*;
 use crate::bank::*;
 use crate::system::*;
 use crate::inputs::*;
+use crate::event::*;
+use crate::outcome::*;
+use crate::rules::*;
+use crate::notation::*;
 
+/// Where the game is up to: whose setup or turn it is, or how the game ended.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum State {
     Setup(PlayerIndex),
     Turn(PlayerIndex, TurnPhase),
     Finished(PlayerIndex), // The winner's index
+    Drawn,
 }
 
-#[derive(Debug)]
+impl State {
+    /// Whose setup or turn it is, unless the game is over.
+    pub fn player(&self) -> Option<PlayerIndex> {
+        match *self {
+            State::Setup(player) | State::Turn(player, _) => Some(player),
+            State::Finished(_) | State::Drawn => None,
+        }
+    }
+}
+
+/// A game of Homeworlds, from the first setup until someone wins or it's drawn. Inputs are
+/// validated in full before they change anything, and every accepted input can be undone.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Game {
-    pub state: State,
+    state: State,
     bank: Bank,
-    systems: Vec<System>,
+    systems: BTreeMap<SystemId, System>,
+    next_system_id: SystemId,
+    rules: RuleSet,
+    eliminated: Vec<PlayerIndex>, // In the order they were eliminated
+    observers: Observers,
+    history: Vec<HistoryEntry>,
+    redo_stack: Vec<Input>,
+}
+
+// Everything an input can change, so that it can be undone.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    state: State,
+    bank: Bank,
+    systems: BTreeMap<SystemId, System>,
+    next_system_id: SystemId,
+    eliminated: Vec<PlayerIndex>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct Position {
+    state: State,
+    bank: Vec<u8>,
+    systems: Vec<SystemPosition>,
+    eliminated: Vec<PlayerIndex>,
+}
+
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct SystemPosition {
+    stars: Vec<Piece>,
+    home_player: Option<PlayerIndex>,
+    ships: Vec<Vec<Piece>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryEntry {
+    input: Input,
+    before: Snapshot,
 }
 
 impl Game {
-    pub fn new() -> Game {
+    /// Panics if the rules aren't valid; see `RuleSet::validate`.
+    pub fn new(rules: RuleSet) -> Game {
+        if let Err(error_message) = rules.validate() {
+            panic!("{}", error_message);
+        }
         Game {
-            bank: Bank::full(),
+            bank: Bank::full(rules.stock),
             state: State::Setup(0),
-            systems: Vec::new(),
+            systems: BTreeMap::new(),
+            next_system_id: 0,
+            rules,
+            eliminated: Vec::new(),
+            observers: Observers::default(),
+            history: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Whose turn it is and what they're up to, or how the game ended.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Every input played so far, not counting any that were undone.
+    pub fn inputs(&self) -> Vec<Input> {
+        self.history.iter().map(|entry| entry.input).collect()
+    }
+
+    /// The inputs that were undone and can still be redone, in the order `redo` would replay them.
+    pub fn undone_inputs(&self) -> Vec<Input> {
+        self.redo_stack.iter().rev().copied().collect()
+    }
+
+    /// The pieces that aren't in play.
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    /// The systems in play, by ID.
+    pub fn systems(&self) -> &BTreeMap<SystemId, System> {
+        &self.systems
+    }
+
+    /// The other systems a ship could move to from the given one. There are none if the system
+    /// doesn't exist.
+    pub fn adjacent_systems(&self, system: SystemId) -> Vec<SystemId> {
+        let system_data = match self.systems.get(&system) {
+            None => return Vec::new(),
+            Some(system_data) => system_data,
+        };
+        self.systems.iter()
+            .filter(|&(&id, other_system)| id != system && system_data.is_adjacent(other_system))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// The sizes of star a ship in the given system could discover, among those the bank still
+    /// has. There are none if the system doesn't exist.
+    pub fn discoverable_sizes(&self, system: SystemId) -> Vec<Size> {
+        let system_data = match self.systems.get(&system) {
+            None => return Vec::new(),
+            Some(system_data) => system_data,
+        };
+        ALL_SIZES.iter().copied()
+            .filter(|&size| ALL_COLORS.iter().any(|&color| self.bank.num_available(Piece { size, color }) > 0))
+            .filter(|&size| system_data.stars().iter().all(|star| star.size != size))
+            .collect()
+    }
+
+    /// The observer hears about every event as it happens, in the same order the events are
+    /// listed in the outcomes returned by the game.
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) -> ObserverId {
+        self.observers.add(observer)
+    }
+
+    /// Whether there was such an observer to remove.
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        self.observers.remove(id)
+    }
+
+    /// Turns this game into the other one, e.g. one loaded from a record, keeping this game's
+    /// observers.
+    pub fn replace(&mut self, other: Game) {
+        let observers = std::mem::take(&mut self.observers);
+        *self = other;
+        self.observers = observers;
+    }
+
+    // Shorthands for `apply` with each kind of input.
+
     pub fn setup(&mut self, setup_move: &SetupMove) -> Result<(), InputError> {
+        self.apply(Input::Setup(*setup_move)).map(|_| ())
+    }
+
+    pub fn free_move(&mut self, system: SystemId, color: Color) -> Result<(), InputError> {
+        self.apply(Input::FreeMove(system, color)).map(|_| ())
+    }
+
+    pub fn sacrifice(&mut self, system: SystemId, ship: Piece) -> Result<ActionOutcome, InputError> {
+        self.apply(Input::Sacrifice(system, ship))
+    }
+
+    pub fn catastrophe(&mut self, system: SystemId, color: Color) -> Result<ActionOutcome, InputError> {
+        self.apply(Input::Catastrophe(system, color))
+    }
+
+    pub fn action(&mut self, action: Action) -> Result<ActionOutcome, InputError> {
+        self.apply(Input::Action(action))
+    }
+
+    pub fn end_turn(&mut self) -> Result<ActionOutcome, InputError> {
+        self.apply(Input::EndTurn)
+    }
+
+    /// Applies the input and records it in the history. Any undone inputs can no longer be redone.
+    pub fn apply(&mut self, input: Input) -> Result<ActionOutcome, InputError> {
+        let outcome = self.apply_and_record(input)?;
+        self.redo_stack.clear();
+        Ok(outcome)
+    }
+
+    /// Applies every input of the turn in order, or none of them: if any is rejected, the game is
+    /// left as it was, no observer hears of the turn, and the error says which input failed. The
+    /// turn has to be a whole one, from the start of a player's turn to the input that ends it.
+    pub fn apply_turn(&mut self, turn: &Turn) -> Result<Vec<ActionOutcome>, TurnError> {
+        if let State::Turn(_, phase) = self.state {
+            if phase != TurnPhase::Started {
+                return Err(TurnError { index: 0, error: InputError::WrongPhase });
+            }
+        }
+        // Rehearse on a copy, which has no observers, so that a rejection comes before any events.
+        let mut rehearsal = self.clone();
+        let mut ended = false;
+        for (index, &input) in turn.inputs.iter().enumerate() {
+            if ended {
+                return Err(TurnError { index, error: InputError::TurnOver });
+            }
+            rehearsal.apply(input).map_err(|error| TurnError { index, error })?;
+            ended = matches!(input, Input::Setup(_) | Input::EndTurn);
+        }
+        if !ended {
+            return Err(TurnError { index: turn.inputs.len().saturating_sub(1), error: InputError::TurnUnfinished });
+        }
+        let outcomes = turn.inputs.iter()
+            .map(|&input| self.apply(input).expect("Rehearsed inputs apply the same way"))
+            .collect();
+        Ok(outcomes)
+    }
+
+    /// Puts the game back exactly as it was before the last input. Returns the undone input.
+    pub fn undo(&mut self) -> Option<Input> {
+        let entry = self.history.pop()?;
+        self.restore(entry.before);
+        self.debug_check_invariants();
+        self.redo_stack.push(entry.input);
+        Some(entry.input)
+    }
+
+    /// Applies the most recently undone input again.
+    pub fn redo(&mut self) -> Option<ActionOutcome> {
+        let input = self.redo_stack.pop()?;
+        let outcome = self.apply_and_record(input).expect("Undone inputs apply again to the same position");
+        Some(outcome)
+    }
+
+    fn apply_and_record(&mut self, input: Input) -> Result<ActionOutcome, InputError> {
+        self.check(&input)?;
+        let before = self.snapshot();
+        let outcome = match self.apply_unchecked(input) {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                // Checked inputs shouldn't fail, but if one does, don't leave it half applied.
+                self.restore(before);
+                return Err(error);
+            },
+        };
+        self.history.push(HistoryEntry { input, before });
+        Ok(outcome)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.state,
+            bank: self.bank.clone(),
+            systems: self.systems.clone(),
+            next_system_id: self.next_system_id,
+            eliminated: self.eliminated.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.state = snapshot.state;
+        self.bank = snapshot.bank;
+        self.systems = snapshot.systems;
+        self.next_system_id = snapshot.next_system_id;
+        self.eliminated = snapshot.eliminated;
+    }
+
+    /// Whether the input would be accepted right now. Every input is validated in full here before
+    /// anything is changed, so that applying it can't fail halfway.
+    pub fn check(&self, input: &Input) -> Result<(), InputError> {
+        match *input {
+            Input::Setup(setup_move) => match self.state {
+                State::Setup(_) => {
+                    self.check_setup_rules(&setup_move)?;
+                    self.bank.check_several(&[setup_move.ship, setup_move.stars[0], setup_move.stars[1]])
+                },
+                _ => Err(InputError::WrongState),
+            },
+            Input::FreeMove(system, color) => {
+                let player = self.started_turn_player()?;
+                self.check_free_move_available(player, system, color)
+            },
+            Input::Sacrifice(system, ship) => {
+                let player = self.started_turn_player()?;
+                match self.systems.get(&system) {
+                    None => Err(InputError::BadSystem(system)),
+                    Some(system_data) if !system_data.has_ship(player, ship) => Err(InputError::NoSuchShip { system, player, ship }),
+                    Some(_) => Ok(()),
+                }
+            },
+            Input::Action(action) => match self.state {
+                State::Turn(player, TurnPhase::FreeMove(system, color)) => {
+                    Game::check_free_move(system, color, action)?;
+                    self.check_action(player, action)
+                },
+                State::Turn(player, TurnPhase::Sacrifice(color, moves_left)) => {
+                    Game::check_sacrifice(color, moves_left, action)?;
+                    self.check_action(player, action)
+                },
+                State::Turn(_, _) => Err(InputError::WrongPhase),
+                _ => Err(InputError::WrongState),
+            },
+            Input::Catastrophe(system, color) => {
+                if let State::Setup(_) | State::Finished(_) | State::Drawn = self.state {
+                    return Err(InputError::WrongState);
+                }
+                let needed = self.rules.catastrophe_count;
+                match self.systems.get(&system) {
+                    None => Err(InputError::BadSystem(system)),
+                    Some(system_data) if system_data.color_count(color) < needed =>
+                        Err(InputError::NotCatastrophicEnough { system, color, count: system_data.color_count(color), needed }),
+                    Some(_) => Ok(()),
+                }
+            },
+            Input::EndTurn => match self.state {
+                State::Turn(_, TurnPhase::Done) | State::Turn(_, TurnPhase::Sacrifice(_, _)) => Ok(()),
+                State::Turn(player, TurnPhase::FreeMove(system, color)) if !self.free_action_possible(player, system, color) => Ok(()),
+                State::Turn(_, _) => Err(InputError::WrongPhase),
+                _ => Err(InputError::WrongState),
+            },
+        }
+    }
+
+    /// Every input that would be accepted right now: each of these passes `check`, and anything
+    /// that passes `check` is one of these.
+    pub fn legal_inputs(&self) -> Vec<Input> {
+        self.candidate_inputs().into_iter().filter(|input| self.check(input).is_ok()).collect()
+    }
+
+    // A superset of the legal inputs, small enough to filter through `check`.
+    fn candidate_inputs(&self) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        let (player, phase) = match self.state {
+            State::Setup(_) => {
+                for first_star in all_pieces() {
+                    for second_star in all_pieces() {
+                        for ship in all_pieces() {
+                            inputs.push(Input::Setup(SetupMove { stars: [first_star, second_star], ship }));
+                        }
+                    }
+                }
+                return inputs;
+            },
+            State::Turn(player, phase) => (player, phase),
+            State::Finished(_) | State::Drawn => return inputs,
+        };
+        match phase {
+            TurnPhase::Started => {
+                for (&system, system_data) in self.systems.iter() {
+                    for &color in ALL_COLORS.iter() {
+                        inputs.push(Input::FreeMove(system, color));
+                    }
+                    for ship in distinct(system_data.get_ships(player)) {
+                        inputs.push(Input::Sacrifice(system, ship));
+                    }
+                }
+            },
+            TurnPhase::FreeMove(system, color) => self.add_candidate_actions(player, &[system], color, &mut inputs),
+            TurnPhase::Sacrifice(color, _) => {
+                let systems: Vec<SystemId> = self.systems.keys().copied().collect();
+                self.add_candidate_actions(player, &systems, color, &mut inputs);
+            },
+            TurnPhase::Done => {},
+        }
+        for &system in self.systems.keys() {
+            for &color in ALL_COLORS.iter() {
+                inputs.push(Input::Catastrophe(system, color));
+            }
+        }
+        inputs.push(Input::EndTurn);
+        inputs
+    }
+
+    // Whether the free action picked can still be taken, which it can't if e.g. there's nothing
+    // to capture, or a catastrophe has since destroyed the system.
+    fn free_action_possible(&self, player: PlayerIndex, system: SystemId, color: Color) -> bool {
+        let mut actions = Vec::new();
+        self.add_candidate_actions(player, &[system], color, &mut actions);
+        actions.iter().any(|action| self.check(action).is_ok())
+    }
+
+    fn add_candidate_actions(&self, player: PlayerIndex, systems: &[SystemId], color: Color, inputs: &mut Vec<Input>) {
+        for &system in systems {
+            let system_data = match self.systems.get(&system) {
+                None => continue,
+                Some(system_data) => system_data,
+            };
+            for ship in distinct(system_data.get_ships(player)) {
+                let mut color_actions = Vec::new();
+                match color {
+                    Color::RED => {
+                        for enemy_player in (0..self.rules.num_players).filter(|&enemy_player| enemy_player != player) {
+                            for ship_to_take in distinct(system_data.get_ships(enemy_player)) {
+                                color_actions.push(ColorAction::RedAction(RedActionInput { enemy_player, ship_to_take }));
+                            }
+                        }
+                    },
+                    Color::BLUE => {
+                        for &new_color in ALL_COLORS.iter() {
+                            color_actions.push(ColorAction::BlueAction(new_color));
+                        }
+                    },
+                    Color::GREEN => color_actions.push(ColorAction::GreenAction),
+                    Color::YELLOW => {
+                        for &other_system in self.systems.keys() {
+                            color_actions.push(ColorAction::YellowAction(YellowActionInput::Existing(other_system)));
+                        }
+                        for new_star in all_pieces() {
+                            color_actions.push(ColorAction::YellowAction(YellowActionInput::Discover(new_star)));
+                        }
+                    },
+                }
+                for color_action in color_actions {
+                    inputs.push(Input::Action(Action { system, ship, color_action }));
+                }
+            }
+        }
+    }
+
+    /// Every distinct way the current player could complete their turn from here, including any
+    /// catastrophes they could trigger along the way. Turns that leave the game in the same
+    /// position count once.
+    pub fn legal_turns(&self) -> Vec<Turn> {
+        self.turn_results().into_iter().map(|(turn, _)| turn).collect()
+    }
+
+    /// The number of leaves in the tree of `legal_turns` played `depth` turns deep from here.
+    /// Positions reached along different branches are each counted; nothing is shared between
+    /// branches.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let results = self.turn_results();
+        if depth == 1 {
+            return results.len() as u64;
+        }
+        results.iter().map(|(_, game)| game.perft(depth - 1)).sum()
+    }
+
+    // Each distinct complete turn along with the game it results in. Searches breadth first, so
+    // each resulting position is reached by one of the shortest turns that leads to it.
+    fn turn_results(&self) -> Vec<(Turn, Game)> {
+        let mut start = self.clone();
+        start.history.clear();
+        start.redo_stack.clear();
+        let mut results = Vec::new();
+        let mut seen_results = HashSet::new();
+        let mut seen_partial_turns = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back((start, Vec::new()));
+        while let Some((game, inputs)) = queue.pop_front() {
+            for input in game.legal_inputs() {
+                let mut next = game.clone();
+                next.apply_unchecked(input).expect("Legal inputs apply");
+                let mut next_inputs = inputs.clone();
+                next_inputs.push(input);
+                let position = next.position();
+                if let Input::Setup(_) | Input::EndTurn = input {
+                    if seen_results.insert(position) {
+                        results.push((Turn { inputs: next_inputs }, next));
+                    }
+                } else if seen_partial_turns.insert(position) {
+                    queue.push_back((next, next_inputs));
+                }
+            }
+        }
+        results
+    }
+
+    // What matters about the game for deciding what happens next, in a canonical form: the order
+    // of pieces within a system, the order of binary stars and the systems' IDs make no
+    // difference. Systems are sorted by what's in them, and a free action refers to its system by
+    // its place in that order.
+    fn position(&self) -> Position {
+        let bank = all_pieces().map(|piece| self.bank.num_available(piece)).collect();
+        let mut systems: Vec<(SystemPosition, SystemId)> = self.systems.iter().map(|(&id, system)| {
+            let mut stars = system.stars();
+            stars.sort();
+            let ships = (0..self.rules.num_players).map(|player| {
+                let mut ships = system.get_ships(player).to_vec();
+                ships.sort();
+                ships
+            }).collect();
+            (SystemPosition { stars, home_player: system.home_player, ships }, id)
+        }).collect();
+        systems.sort();
+        let state = match self.state {
+            State::Turn(player, TurnPhase::FreeMove(system, color)) => {
+                // A system that's gone sorts after every system that's left.
+                let place = systems.iter().position(|&(_, id)| id == system).unwrap_or(systems.len());
+                State::Turn(player, TurnPhase::FreeMove(place as SystemId, color))
+            },
+            state => state,
+        };
+        let mut eliminated = self.eliminated.clone();
+        eliminated.sort_unstable();
+        Position { state, bank, systems: systems.into_iter().map(|(system, _)| system).collect(), eliminated }
+    }
+
+    fn started_turn_player(&self) -> Result<PlayerIndex, InputError> {
         match self.state {
-            State::Setup(player) => self.setup_unchecked(player, setup_move),
+            State::Turn(player, TurnPhase::Started) => Ok(player),
+            State::Turn(_, _) => Err(InputError::WrongPhase),
             _ => Err(InputError::WrongState),
         }
     }
 
+    // Applies an input that has already passed `check`.
+    fn apply_unchecked(&mut self, input: Input) -> Result<ActionOutcome, InputError> {
+        let mut outcome = ActionOutcome::new();
+        match (input, self.state) {
+            (Input::Setup(setup_move), State::Setup(player)) => self.setup_unchecked(player, &setup_move)?,
+            (Input::FreeMove(system, color), State::Turn(player, _)) =>
+                self.state = State::Turn(player, TurnPhase::FreeMove(system, color)),
+            (Input::Sacrifice(system, ship), State::Turn(player, _)) => self.sacrifice_unchecked(player, system, ship, &mut outcome)?,
+            (Input::Action(action), State::Turn(player, phase)) => {
+                self.action_unchecked(player, action, &mut outcome)?;
+                self.state = match phase {
+                    TurnPhase::Sacrifice(color, moves_left) if moves_left > 1 =>
+                        State::Turn(player, TurnPhase::Sacrifice(color, moves_left - 1)),
+                    _ => State::Turn(player, TurnPhase::Done),
+                };
+            },
+            (Input::Catastrophe(system, color), _) => self.catastrophe_unchecked(system, color, &mut outcome)?,
+            (Input::EndTurn, State::Turn(player, _)) => self.end_turn_unchecked(player, &mut outcome)?,
+            _ => unreachable!("Input {:?} was checked against state {:?}", input, self.state),
+        }
+        self.debug_check_invariants();
+        Ok(outcome)
+    }
+
     fn setup_unchecked(&mut self, player: PlayerIndex, setup_move: &SetupMove) -> Result<(), InputError> {
         self.bank.remove_several(&[setup_move.ship, setup_move.stars[0], setup_move.stars[1]])?;
 
         let mut homeworld = System::new_homeworld(setup_move.stars, player);
         homeworld.add_ship(player, setup_move.ship);
-        self.systems.push(homeworld);
+        self.add_system(homeworld);
 
         let next_player = player + 1;
-        if next_player < NUM_PLAYERS {
+        if next_player < self.rules.num_players {
             self.state = State::Setup(next_player);
         } else {
             self.state = State::Turn(0, TurnPhase::Started);
@@ -50,229 +558,466 @@ impl Game {
         Ok(())
     }
 
-    pub fn free_move(&mut self, system: SystemIndex, color: Color) -> Result<(), InputError> {
-        match self.state {
-            State::Turn(player, TurnPhase::Started) => {
-                self.check_free_move_available(player, system, color)?;
-                self.state = State::Turn(player, TurnPhase::FreeMove(system, color));
-                return Ok(());
-            },
-            State::Turn(_, _) => Err(InputError::WrongPhase),
-            _ => Err(InputError::WrongState),
+    fn sacrifice_unchecked(&mut self, player: PlayerIndex, system: SystemId, ship: Piece, outcome: &mut ActionOutcome)
+        -> Result<(), InputError> {
+        self.remove_ship(system, player, ship)?;
+        self.bank.add(ship)?;
+        self.emit(outcome, GameEvent::ShipSacrificed { system, player, ship });
+        self.evaporate_system_if_necessary(system, outcome)?;
+        self.state = State::Turn(player, TurnPhase::Sacrifice(ship.color, ship.size.to_u8()));
+        Ok(())
+    }
+
+    fn catastrophe_unchecked(&mut self, system: SystemId, color: Color, outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        let result = self.systems.get_mut(&system).unwrap().catastrophe(color, &mut self.bank)?;
+        for (player, ship) in result.destroyed_ships {
+            self.emit(outcome, GameEvent::ShipDestroyed { system, player, ship });
+        }
+        for star in result.destroyed_stars {
+            self.emit(outcome, GameEvent::StarDestroyed { system, star });
+        }
+        if result.evaporated {
+            self.systems.remove(&system);
+            self.emit(outcome, GameEvent::SystemEvaporated(system));
         }
+        Ok(())
     }
 
-    pub fn sacrifice(&mut self, system: SystemIndex, ship: Piece) -> Result<(), InputError> {
-        match self.state {
-            State::Turn(player, TurnPhase::Started) => {
-                let system_data = self.systems.get_mut(system as usize);
-                match system_data {
-                    None => Err(InputError::BadSystem),
-                    Some(system_data) => {
-                        system_data.remove_ship(player, ship)?;
-                        self.evaporate_system_if_necessary(system);
-                        self.state = State::Turn(player, TurnPhase::Sacrifice(ship.color, ship.size.to_u8()));
-                        self.end_game_if_necessary();
-                        Ok(())
-                    }
-                }
-            },
-            State::Turn(_, _) => Err(InputError::WrongPhase),
-            _ => Err(InputError::WrongState),
+    // Losses are only evaluated here, so a player may leave their homeworld mid-turn as long
+    // as they're back by the time the turn ends. Unused sacrifice actions may be forfeited, and so
+    // may a free action that can't be taken.
+    fn end_turn_unchecked(&mut self, player: PlayerIndex, outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        if !self.end_game_if_necessary(player, outcome)? {
+            self.state = State::Turn(self.left_of(player), TurnPhase::Started);
         }
+        Ok(())
     }
 
-    pub fn catastrophe(&mut self, system: SystemIndex, color: Color) -> Result<(), InputError> {
-        if let State::Setup(_) | State::Finished(_) = self.state {
-            return Err(InputError::WrongState);
+    /// Every piece must be somewhere, exactly as many times as the bank started with, and the board
+    /// must make sense. Describes the first problem found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for piece in all_pieces() {
+            let on_board: usize = self.systems.values().map(|system| system.count_piece(piece)).sum();
+            let total = on_board + usize::from(self.bank.num_available(piece));
+            if total != usize::from(self.rules.stock) {
+                return Err(format!("There are {} {} pieces instead of {}", total, piece, self.rules.stock));
+            }
         }
-        let system_data = self.systems.get_mut(system as usize);
-        match system_data {
-            None => Err(InputError::BadSystem),
-            Some(system_data) => {
-                let color_count = system_data.color_count(color);
-                if color_count < CATASTROPHE_COUNT {
-                    return Err(InputError::NotCatastrophicEnough);
+        // Losers of the final turn keep their homeworlds on the final board.
+        let game_over = matches!(self.state, State::Finished(_) | State::Drawn);
+        let mut home_players = Vec::new();
+        for (id, system) in self.systems.iter() {
+            if system.is_empty() {
+                return Err(format!("System {} is empty", id));
+            }
+            if let Some(home_player) = system.home_player {
+                if home_player >= self.rules.num_players || (!game_over && self.is_eliminated(home_player)) {
+                    return Err(format!("System {} is the homeworld of player {}, who isn't playing", id, home_player));
                 }
-                let result = system_data.catastrophe(color, &mut self.bank);
-                if let CatastropheResult::SystemEvaporated = result {
-                    self.systems.remove(system as usize);
+                if home_players.contains(&home_player) {
+                    return Err(format!("Player {} has more than one homeworld", home_player));
                 }
-                self.end_game_if_necessary();
-                Ok(())
+                home_players.push(home_player);
             }
         }
+        Ok(())
     }
 
-    // TODO this should be able to return something, at least for yellow discoveries
-    pub fn action(&mut self, action: Action) -> Result<(), InputError> {
-        match self.state {
-            State::Turn(player, TurnPhase::FreeMove(system, color)) => {
-                Game::check_free_move(system, color, action)?;
-                self.check_action(player, action)?;
-                self.action_unchecked(player, action)?;
-                self.state = State::Turn(player, TurnPhase::Done);
-                self.end_game_if_necessary();
-                Ok(())
+    /// The whole position on one line, as described in the `notation` module. Doesn't include
+    /// the rules or the history.
+    pub fn to_notation(&self) -> String {
+        let bank: Vec<String> = all_pieces().map(|piece| self.bank.num_available(piece).to_string()).collect();
+        let eliminated = if self.eliminated.is_empty() {
+            "-".to_string()
+        } else {
+            self.eliminated.iter().map(|player| player.to_string()).collect::<Vec<String>>().join(",")
+        };
+        let mut fields = vec![
+            state_notation(self.state),
+            format!("bank:{}", bank.join(",")),
+            format!("out:{}", eliminated),
+            format!("next:{}", self.next_system_id),
+        ];
+        for (id, system) in self.systems.iter() {
+            let mut field = format!("{}:{}", id, pieces_notation(&system.stars()));
+            if let Some(home_player) = system.home_player {
+                field.push_str(&format!("@{}", home_player));
+            }
+            for &player in system.ships().keys() {
+                field.push_str(&format!("/{}:{}", player, pieces_notation(system.get_ships(player))));
+            }
+            fields.push(field);
+        }
+        fields.join(" ")
+    }
+
+    /// Reads a position written by `to_notation`, to be played under the given rules. The game
+    /// starts out with no history. Invalid rules, and positions that couldn't come up under the
+    /// rules, are rejected.
+    pub fn from_notation(notation: &str, rules: RuleSet) -> Result<Game, String> {
+        rules.validate()?;
+        let mut game = Game::new(rules);
+        let check_player = |player: PlayerIndex| {
+            if player >= rules.num_players {
+                return Err(format!("There is no player {} in a {} player game", player, rules.num_players));
+            }
+            Ok(player)
+        };
+        let mut fields = notation.split_whitespace();
+        let mut next_field = |prefix: &str| match fields.next() {
+            None => Err(format!("The position has no {} field", prefix)),
+            Some(field) => match field.strip_prefix(prefix) {
+                None => Err(format!("Expected the {} field, not {}", prefix, field)),
+                Some(value) => Ok(value),
             },
-            State::Turn(player, TurnPhase::Sacrifice(color, moves_left)) => {
-                Game::check_sacrifice(color, moves_left, action)?;
-                self.check_action(player, action)?;
-                self.action_unchecked(player, action)?;
-                let moves_left = moves_left - 1;
-                self.state = if moves_left > 0 {
-                    State::Turn(player, TurnPhase::Sacrifice(color, moves_left))
-                } else {
-                    State::Turn(player, TurnPhase::Done)
-                };
-                self.end_game_if_necessary();
-                Ok(())
+        };
+        let state = next_field("")?;
+        game.state = parse_state(state)?;
+
+        let bank = next_field("bank:")?.split(',').collect::<Vec<&str>>();
+        if bank.len() != all_pieces().count() {
+            return Err(format!("The bank lists {} pieces instead of {}", bank.len(), all_pieces().count()));
+        }
+        game.bank = Bank::empty(rules.stock);
+        for (piece, amount) in all_pieces().zip(bank) {
+            for _ in 0..parse_number::<u8>(amount, "number of pieces")? {
+                game.bank.add(piece).map_err(|error| error.to_string())?;
+            }
+        }
+
+        let eliminated = next_field("out:")?;
+        if eliminated != "-" {
+            for player in eliminated.split(',') {
+                let player = check_player(parse_number(player, "player")?)?;
+                if game.eliminated.contains(&player) {
+                    return Err(format!("Player {} is eliminated twice", player));
+                }
+                game.eliminated.push(player);
+            }
+        }
+        game.next_system_id = parse_number(next_field("next:")?, "system ID")?;
+
+        let mut ownerless_binaries = 0;
+        for field in fields {
+            let mut parts = field.split('/');
+            let (id, stars) = parts.next().unwrap().split_once(':')
+                .ok_or_else(|| format!("Expected a system ID and stars: {}", field))?;
+            let id: SystemId = parse_number(id, "system ID")?;
+            if id >= game.next_system_id {
+                return Err(format!("System {} can't exist when the next system ID is {}", id, game.next_system_id));
+            }
+            let (stars, home_player) = match stars.split_once('@') {
+                None => (stars, None),
+                Some((stars, home_player)) => (stars, Some(check_player(parse_number(home_player, "player")?)?)),
+            };
+            let mut system = match *parse_pieces(stars)?.as_slice() {
+                [star] => {
+                    let mut system = System::new(star);
+                    system.home_player = home_player;
+                    system
+                },
+                [star, second_star] => System::new_binary([star, second_star], home_player),
+                _ => return Err(format!("System {} has the wrong number of stars", id)),
+            };
+            // Only an eliminated player's homeworld keeps both stars without an owner.
+            if system.stars().len() == 2 && home_player.is_none() {
+                ownerless_binaries += 1;
+                if game.eliminated.is_empty() {
+                    return Err(format!("System {} has two stars but isn't a homeworld, and nobody has been eliminated", id));
+                }
+                if ownerless_binaries > game.eliminated.len() {
+                    return Err(format!("System {} has two stars but isn't a homeworld, and every eliminated player's homeworld is accounted for", id));
+                }
+            }
+            for ships in parts {
+                let (player, ships) = ships.split_once(':')
+                    .ok_or_else(|| format!("Expected a player and their ships in system {}: {}", id, ships))?;
+                let player = check_player(parse_number(player, "player")?)?;
+                if !system.get_ships(player).is_empty() {
+                    return Err(format!("Player {}'s ships are listed twice in system {}", player, id));
+                }
+                for ship in parse_pieces(ships)? {
+                    system.add_ship(player, ship);
+                }
+            }
+            if game.systems.insert(id, system).is_some() {
+                return Err(format!("System {} is listed twice", id));
+            }
+        }
+
+        match game.state {
+            State::Setup(player) => {
+                check_player(player)?;
+                let set_up = game.systems.values().filter(|system| system.home_player.is_some_and(|home_player| home_player < player));
+                if set_up.count() != usize::from(player) || game.systems.len() != usize::from(player) {
+                    return Err(format!("Only the homeworlds of players before player {} can exist during their setup", player));
+                }
+                if game.next_system_id != SystemId::from(player) {
+                    return Err(format!("The next system ID during player {}'s setup is {}, not {}", player, player, game.next_system_id));
+                }
             },
-            State::Turn(_, _) => Err(InputError::WrongPhase),
-            _ => Err(InputError::WrongState),
+            State::Turn(player, phase) => {
+                if game.is_eliminated(check_player(player)?) {
+                    return Err(format!("It can't be player {}'s turn, they've been eliminated", player));
+                }
+                // The free action's system may have been destroyed since, but it must have existed.
+                if let TurnPhase::FreeMove(system, _) = phase {
+                    if system >= game.next_system_id {
+                        return Err(format!("The free action is in system {}, which can't exist when the next system ID is {}",
+                                           system, game.next_system_id));
+                    }
+                }
+            },
+            State::Finished(winner) => {
+                check_player(winner)?;
+            },
+            State::Drawn => {},
         }
+        game.check_invariants()?;
+        Ok(game)
     }
 
-    pub fn end_turn(&mut self) -> Result<(), InputError> {
-        match self.state {
-            State::Turn(player, TurnPhase::Done) => {
-                let next_player = (player + 1) % NUM_PLAYERS;
-                self.state = State::Turn(next_player, TurnPhase::Started);
-                Ok(())
-            },
-            State::Turn(_, _) => Err(InputError::WrongPhase),
-            _ => Err(InputError::WrongState),
+    fn debug_check_invariants(&self) {
+        if cfg!(debug_assertions) {
+            if let Err(error_message) = self.check_invariants() {
+                panic!("{}\n{}", error_message, self);
+            }
+        }
+    }
+
+    /// The players who are out, in the order they went out.
+    pub fn eliminated(&self) -> &[PlayerIndex] {
+        &self.eliminated
+    }
+
+    pub fn is_eliminated(&self, player: PlayerIndex) -> bool {
+        self.eliminated.contains(&player)
+    }
+
+    /// The next player still in the game after the given one, in seat order. That's who moves
+    /// next, and the only player they may attack.
+    pub fn left_of(&self, player: PlayerIndex) -> PlayerIndex {
+        (1..=self.rules.num_players)
+            .map(|offset| (player + offset) % self.rules.num_players)
+            .find(|&other_player| !self.is_eliminated(other_player))
+            .unwrap()
+    }
+
+    fn live_players(&self) -> Vec<PlayerIndex> {
+        (0..self.rules.num_players).filter(|&player| !self.is_eliminated(player)).collect()
+    }
+
+    // An eliminated player's ships all go back to the bank, emptied systems evaporate, and their
+    // homeworld, if it's still held by other players, becomes an ordinary system.
+    fn eliminate(&mut self, player: PlayerIndex, outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        self.eliminated.push(player);
+        let systems: Vec<SystemId> = self.systems.keys().copied().collect();
+        for system in systems {
+            let system_data = self.systems.get_mut(&system).unwrap();
+            if system_data.home_player == Some(player) {
+                system_data.home_player = None;
+            }
+            let ships = system_data.remove_all_ships(player);
+            if ships.is_empty() {
+                continue;
+            }
+            for ship in ships {
+                self.bank.add(ship)?;
+            }
+            self.evaporate_system_if_necessary(system, outcome)?;
         }
+        Ok(())
     }
 
     fn check_action(&self, player: PlayerIndex, action: Action) -> Result<(), InputError> {
-        let system = self.systems.get(action.system as usize);
+        let system = self.systems.get(&action.system);
         match system {
-            None => Err(InputError::BadSystem),
+            None => Err(InputError::BadSystem(action.system)),
             Some(system) => {
                 if !system.has_ship(player, action.ship) {
-                    return Err(InputError::NoSuchShip);
+                    return Err(InputError::NoSuchShip { system: action.system, player, ship: action.ship });
+                }
+                match action.color_action {
+                    ColorAction::RedAction(red_action_input) =>
+                        self.check_red_action(player, action.system, system, action.ship, &red_action_input),
+                    ColorAction::BlueAction(new_color) => self.check_blue_action(action.ship, new_color),
+                    ColorAction::GreenAction => self.get_green_ship(action.ship).map(|_| ()),
+                    ColorAction::YellowAction(yellow_action_input) =>
+                        self.check_yellow_action(action.system, system, &yellow_action_input),
                 }
-                Ok(())
             }
         }
     }
 
-    fn action_unchecked(&mut self, player: PlayerIndex, action: Action) -> Result<(), InputError> {
+    fn action_unchecked(&mut self, player: PlayerIndex, action: Action, outcome: &mut ActionOutcome) -> Result<(), InputError> {
         match action.color_action {
-            ColorAction::RedAction(red_action_input) => self.red_action(player, action.system, action.ship, &red_action_input),
-            ColorAction::BlueAction(new_color) => self.blue_action(player, action.system, action.ship, new_color),
-            ColorAction::GreenAction => self.green_action(player, action.system, action.ship),
-            ColorAction::YellowAction(yellow_action_input) => self.yellow_action(player, action.system, action.ship, &yellow_action_input),
+            ColorAction::RedAction(red_action_input) => self.red_action(player, action.system, &red_action_input, outcome),
+            ColorAction::BlueAction(new_color) => self.blue_action(player, action.system, action.ship, new_color, outcome),
+            ColorAction::GreenAction => self.green_action(player, action.system, action.ship, outcome),
+            ColorAction::YellowAction(yellow_action_input) =>
+                self.yellow_action(player, action.system, action.ship, &yellow_action_input, outcome),
         }
     }
 
-    fn red_action(&mut self, player: PlayerIndex, system: SystemIndex, ship: Piece, RedActionInput { enemy_player, ship_to_take }: &RedActionInput) -> Result<(), InputError> {
-        if player == *enemy_player {
-            return Err(InputError::WrongPlayer);
+    fn check_red_action(&self, player: PlayerIndex, system_id: SystemId, system: &System, ship: Piece,
+                        RedActionInput { enemy_player, ship_to_take }: &RedActionInput) -> Result<(), InputError> {
+        let target = self.left_of(player);
+        if *enemy_player != target || *enemy_player == player {
+            return Err(InputError::WrongPlayer { enemy_player: *enemy_player, target });
         }
         if ship_to_take.size > ship.size {
-            return Err(InputError::ShipTooBig);
+            return Err(InputError::ShipTooBig { ship, ship_to_take: *ship_to_take });
+        }
+        if !system.has_ship(*enemy_player, *ship_to_take) {
+            return Err(InputError::NoSuchShip { system: system_id, player: *enemy_player, ship: *ship_to_take });
         }
-        let system = self.systems.get_mut(system as usize).unwrap();
-        system.remove_ship(*enemy_player, *ship_to_take)?;
-        system.add_ship(player, *ship_to_take);
         Ok(())
     }
 
-    fn blue_action(&mut self, player: PlayerIndex, system: SystemIndex, ship: Piece, new_color: Color)
-        -> Result<(), InputError> {
-        if ship.color == new_color {
-            return Err(InputError::WrongColor);
-        }
-        let new_ship = Piece { color: new_color, size: ship.size };
-        if self.bank.num_available(new_ship) <= 0 {
-            return Err(InputError::PieceUnavailable);
-        }
-        let system = self.systems.get_mut(system as usize).unwrap();
-        system.remove_ship(player, ship)?;
-        self.bank.add(ship);
-        self.bank.remove(new_ship);
-        system.add_ship(player, new_ship);
+    fn red_action(&mut self, player: PlayerIndex, system: SystemId, RedActionInput { enemy_player, ship_to_take }: &RedActionInput,
+                  outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        self.remove_ship(system, *enemy_player, *ship_to_take)?;
+        self.systems.get_mut(&system).unwrap().add_ship(player, *ship_to_take);
+        self.emit(outcome, GameEvent::ShipCaptured { system, player, enemy_player: *enemy_player, ship: *ship_to_take });
         Ok(())
     }
 
-    fn green_action(&mut self, player: PlayerIndex, system: SystemIndex, ship: Piece) -> Result<(), InputError> {
-        let system = self.systems.get_mut(system as usize).unwrap();
-        let possible_new_ships = [
-            Piece { color: ship.color, size: Size::SMALL },
-            Piece { color: ship.color, size: Size::MEDIUM },
-            Piece { color: ship.color, size: Size::LARGE }];
-        for new_ship in possible_new_ships.iter() {
-            if self.bank.num_available(*new_ship) > 0 {
-                self.bank.remove(*new_ship);
-                system.add_ship(player, *new_ship);
-                return Ok(());
-            }
+    fn check_blue_action(&self, ship: Piece, new_color: Color) -> Result<(), InputError> {
+        if ship.color == new_color {
+            return Err(InputError::WrongColor(new_color));
+        }
+        let new_ship = Piece { color: new_color, size: ship.size };
+        if self.bank.num_available(new_ship) == 0 {
+            return Err(InputError::PieceUnavailable { piece: new_ship, available: 0 });
         }
-        Err(InputError::PieceUnavailable)
+        Ok(())
     }
 
-    fn yellow_action(&mut self, player: PlayerIndex, system: SystemIndex, ship: Piece, yellow_action_input: &YellowActionInput)
+    fn blue_action(&mut self, player: PlayerIndex, system: SystemId, ship: Piece, new_color: Color, outcome: &mut ActionOutcome)
         -> Result<(), InputError> {
-        let target_system = self.get_yellow_target(system, yellow_action_input)?;
-        target_system.add_ship(player, ship);
-        let system_data = self.systems.get_mut(system as usize).unwrap();
-        system_data.remove_ship(player, ship);
-        self.evaporate_system_if_necessary(system);
+        let new_ship = Piece { color: new_color, size: ship.size };
+        self.bank.remove(new_ship)?;
+        self.bank.add(ship)?;
+        self.remove_ship(system, player, ship)?;
+        self.systems.get_mut(&system).unwrap().add_ship(player, new_ship);
+        self.emit(outcome, GameEvent::ShipTraded { system, player, old_ship: ship, new_ship });
         Ok(())
     }
 
-    fn evaporate_system_if_necessary(&mut self, system: SystemIndex) {
-        let system_data = self.systems.get_mut(system as usize).unwrap();
-        if system_data.is_empty() {
-            for &star in system_data.stars().iter() {
-                self.bank.add(star);
-            }
-            self.systems.remove(system as usize);
-        }
+    // The smallest ship of the building ship's color left in the bank.
+    fn get_green_ship(&self, ship: Piece) -> Result<Piece, InputError> {
+        ALL_SIZES.iter()
+            .map(|&size| Piece { color: ship.color, size })
+            .find(|&new_ship| self.bank.num_available(new_ship) > 0)
+            .ok_or(InputError::ColorUnavailable(ship.color))
+    }
+
+    fn green_action(&mut self, player: PlayerIndex, system: SystemId, ship: Piece, outcome: &mut ActionOutcome)
+        -> Result<(), InputError> {
+        let new_ship = self.get_green_ship(ship)?;
+        self.bank.remove(new_ship)?;
+        self.systems.get_mut(&system).unwrap().add_ship(player, new_ship);
+        self.emit(outcome, GameEvent::ShipBuilt { system, player, ship: new_ship });
+        Ok(())
     }
 
-    fn get_yellow_target(&mut self, system: SystemIndex, yellow_action_input: &YellowActionInput)
-        -> Result<&mut System, InputError> {
-        let system = self.systems.get(system as usize).unwrap();
+    fn check_yellow_action(&self, system_id: SystemId, system: &System, yellow_action_input: &YellowActionInput)
+        -> Result<(), InputError> {
         match yellow_action_input {
-            YellowActionInput::Existing(existing_system_index) => {
-                let existing_system = self.systems.get(*existing_system_index as usize);
+            YellowActionInput::Existing(existing_system_id) => {
+                let existing_system = self.systems.get(existing_system_id);
                 match existing_system {
-                    None => Err(InputError::BadSystem),
+                    None => Err(InputError::BadSystem(*existing_system_id)),
                     Some(existing_system) => {
                         if !system.is_adjacent(existing_system) {
-                            return Err(InputError::SystemsNotAdjacent);
+                            return Err(InputError::SystemsNotAdjacent { from: system_id, to: *existing_system_id });
                         }
-                        Ok(self.systems.get_mut(*existing_system_index as usize).unwrap())
+                        Ok(())
                     }
                 }
             },
             YellowActionInput::Discover(new_star) => {
-                if self.bank.num_available(*new_star) <= 0 {
-                    return Err(InputError::PieceUnavailable);
+                if self.bank.num_available(*new_star) == 0 {
+                    return Err(InputError::PieceUnavailable { piece: *new_star, available: 0 });
                 }
-                let new_system = System::new(*new_star);
-                if !system.is_adjacent(&new_system) {
-                    return Err(InputError::SystemsNotAdjacent);
+                if !system.is_adjacent(&System::new(*new_star)) {
+                    return Err(InputError::StarNotAdjacent { from: system_id, star: *new_star });
                 }
-                self.bank.remove(*new_star);
-                self.systems.push(new_system);
-                Ok(self.systems.last_mut().unwrap())
+                Ok(())
             },
         }
     }
 
-    fn check_free_move_available(&self, player: PlayerIndex, system: SystemIndex, color: Color) -> Result<(), InputError> {
-        let system = self.systems.get(system as usize);
-        if let None = system {
-            return Err(InputError::BadSystem);
+    fn yellow_action(&mut self, player: PlayerIndex, system: SystemId, ship: Piece, yellow_action_input: &YellowActionInput,
+                     outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        let target_system = match yellow_action_input {
+            YellowActionInput::Existing(existing_system_id) => *existing_system_id,
+            YellowActionInput::Discover(new_star) => {
+                self.bank.remove(*new_star)?;
+                let new_system = self.add_system(System::new(*new_star));
+                self.emit(outcome, GameEvent::SystemDiscovered { system: new_system, star: *new_star });
+                new_system
+            },
+        };
+        self.remove_ship(system, player, ship)?;
+        self.systems.get_mut(&target_system).unwrap().add_ship(player, ship);
+        self.emit(outcome, GameEvent::ShipMoved { player, ship, from: system, to: target_system });
+        self.evaporate_system_if_necessary(system, outcome)?;
+        Ok(())
+    }
+
+    fn emit(&mut self, outcome: &mut ActionOutcome, event: GameEvent) {
+        self.observers.notify(&event);
+        outcome.push(event);
+    }
+
+    fn remove_ship(&mut self, system: SystemId, player: PlayerIndex, ship: Piece) -> Result<(), InputError> {
+        if self.systems.get_mut(&system).unwrap().remove_ship(player, ship) {
+            Ok(())
+        } else {
+            Err(InputError::NoSuchShip { system, player, ship })
+        }
+    }
+
+    fn add_system(&mut self, system: System) -> SystemId {
+        let id = self.next_system_id;
+        self.next_system_id += 1;
+        self.systems.insert(id, system);
+        id
+    }
+
+    fn evaporate_system_if_necessary(&mut self, system: SystemId, outcome: &mut ActionOutcome) -> Result<(), InputError> {
+        let system_data = self.systems.get(&system).unwrap();
+        if system_data.is_empty() {
+            for &star in system_data.stars().iter() {
+                self.bank.add(star)?;
+            }
+            self.systems.remove(&system);
+            self.emit(outcome, GameEvent::SystemEvaporated(system));
+        }
+        Ok(())
+    }
+
+    fn check_setup_rules(&self, setup_move: &SetupMove) -> Result<(), InputError> {
+        let setup_rules = self.rules.setup;
+        if setup_rules.large_starting_ship && setup_move.ship.size != Size::LARGE {
+            return Err(InputError::SetupNotAllowed(SetupRule::LargeStartingShip));
+        }
+        if setup_rules.distinct_star_sizes && setup_move.stars[0].size == setup_move.stars[1].size {
+            return Err(InputError::SetupNotAllowed(SetupRule::DistinctStarSizes));
+        }
+        Ok(())
+    }
+
+    fn check_free_move_available(&self, player: PlayerIndex, system_id: SystemId, color: Color) -> Result<(), InputError> {
+        let system = self.systems.get(&system_id);
+        if system.is_none() {
+            return Err(InputError::BadSystem(system_id));
         }
         let system = system.unwrap();
         let available_ships = system.get_ships(player);
         if available_ships.is_empty() {
-            return Err(InputError::FreeActionUnavailable);
+            return Err(InputError::FreeActionUnavailable { system: system_id, player, color });
         }
         for star in system.stars().iter() {
             if star.color == color {
@@ -282,64 +1027,79 @@ impl Game {
         if available_ships.iter().any(|ship| ship.color == color) {
             return Ok(());
         }
-        return Err(InputError::FreeActionUnavailable);
+        Err(InputError::FreeActionUnavailable { system: system_id, player, color })
     }
 
-    fn check_free_move(system: SystemIndex, color: Color, action: Action) -> Result<(), InputError> {
+    fn check_free_move(system: SystemId, color: Color, action: Action) -> Result<(), InputError> {
         if system != action.system {
-            return Err(InputError::WrongSystem);
+            return Err(InputError::WrongSystem { expected: system, actual: action.system });
         }
         Game::check_action_color(color, action.color_action)
     }
 
     fn check_sacrifice(color: Color, moves_left: u8, action: Action) -> Result<(), InputError> {
-        if moves_left <= 0 {
+        if moves_left == 0 {
             return Err(InputError::NoActionsLeft);
         }
         Game::check_action_color(color, action.color_action)
     }
 
     fn check_action_color(color: Color, color_action: ColorAction) -> Result<(), InputError> {
-        match color_action {
-            ColorAction::RedAction(_) if color != Color::RED => Err(InputError::WrongActionColor),
-            ColorAction::BlueAction(_) if color != Color::BLUE => Err(InputError::WrongActionColor),
-            ColorAction::GreenAction if color != Color::GREEN => Err(InputError::WrongActionColor),
-            ColorAction::YellowAction(_) if color != Color::YELLOW => Err(InputError::WrongActionColor),
-            _ => Ok(())
+        if color_action.color() != color {
+            return Err(InputError::WrongActionColor { expected: color, actual: color_action.color() });
         }
+        Ok(())
     }
 
-    fn end_game_if_necessary(&mut self) -> bool {
-        let winner = self.get_winner();
-        if let Some(winner) = winner {
-            self.state = State::Finished(winner);
-            true
+    // Whoever has lost their homeworld, or every ship at it, by the end of a turn is out. The
+    // mover wins by knocking out the player to their left; otherwise the game goes on until only
+    // one player is left. The mutual destruction rule only settles things when nobody else is.
+    fn end_game_if_necessary(&mut self, mover: PlayerIndex, outcome: &mut ActionOutcome) -> Result<bool, InputError> {
+        let target = self.left_of(mover);
+        let losers: Vec<PlayerIndex> = self.live_players().into_iter().filter(|&player| self.is_loser(player)).collect();
+        let survivors: Vec<PlayerIndex> = self.live_players().into_iter().filter(|player| !losers.contains(player)).collect();
+        let result = if !losers.contains(&target) || losers.contains(&mover) && !survivors.is_empty() {
+            if survivors.len() == 1 { Some(State::Finished(survivors[0])) } else { None }
+        } else if !losers.contains(&mover) {
+            Some(State::Finished(mover))
         } else {
-            false
+            match self.rules.mutual_destruction {
+                MutualDestruction::Draw => Some(State::Drawn),
+                MutualDestruction::MoverLoses => Some(State::Finished(target)),
+            }
+        };
+        for &loser in losers.iter() {
+            if result.is_some() {
+                // The final board stays as it was.
+                self.eliminated.push(loser);
+            } else {
+                self.eliminate(loser, outcome)?;
+            }
+            self.emit(outcome, GameEvent::PlayerEliminated(loser));
         }
-    }
-
-    fn get_winner(&self) -> Option<PlayerIndex> {
-        let non_losers: Vec<PlayerIndex> = (0..NUM_PLAYERS).into_iter().filter(|&player| !self.is_loser(player)).collect();
-        return if non_losers.len() == 1 {
-            Some(*non_losers.get(0).unwrap())
-        } else {
-            None
+        match result {
+            None => Ok(false),
+            Some(result) => {
+                self.state = result;
+                match result {
+                    State::Finished(winner) => self.emit(outcome, GameEvent::GameWon(winner)),
+                    _ => self.emit(outcome, GameEvent::GameDrawn),
+                }
+                Ok(true)
+            },
         }
     }
 
     fn is_loser(&self, player: PlayerIndex) -> bool {
-        let system_loss_status = self.systems.iter()
+        let system_loss_status = self.systems.values()
             .filter(|system| match system.home_player {
                 None => false,
                 Some(home_player) => home_player == player,
             })
             .map(|home_system| home_system.get_ships(home_system.home_player.unwrap()).is_empty())
             .next();
-        match system_loss_status {
-            None => true, // No home system, no ships at home system. Lose.
-            Some(is_loser) => is_loser,
-        }
+        // No home system, no ships at home system. Lose.
+        system_loss_status.unwrap_or(true)
     }
 }
 
@@ -348,27 +1108,990 @@ impl fmt::Display for State {
         match self {
             State::Setup(player) => write!(f, "Player {}'s setup", player),
             State::Finished(winner) => write!(f, "Game over, player {} wins", winner),
-            State::Turn(player, turn_phase) => {
-                write!(f, "Player {}'s turn, ", player)?;
-                match turn_phase {
-                    TurnPhase::Started => write!(f, "no move selected"),
-                    TurnPhase::Done => write!(f, "no moves left"),
-                    TurnPhase::FreeMove(system, color) =>
-                        write!(f, "free {} move in system {}", color, system),
-                    TurnPhase::Sacrifice(color, moves_left) =>
-                        write!(f, "{} sacrifice, {} move(s) left", color, moves_left),
-                }
-            },
+            State::Drawn => write!(f, "Game over, draw"),
+            State::Turn(player, turn_phase) => write!(f, "Player {}'s turn, {}", player, turn_phase),
         }
     }
 }
 
 impl fmt::Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}\n{}\nSystems:\n", self.bank, self.state)?;
-        for (index, system) in self.systems.iter().enumerate() {
-            write!(f, "{}: {}\n", index, system)?;
+        writeln!(f, "{}\n{}", self.bank, self.state)?;
+        for player in self.eliminated.iter() {
+            writeln!(f, "Player {} has been eliminated", player)?;
+        }
+        writeln!(f, "Systems:")?;
+        for (id, system) in self.systems.iter() {
+            writeln!(f, "{}: {}", id, system)?;
         }
         Ok(())
     }
 }
+
+fn distinct(pieces: &[Piece]) -> Vec<Piece> {
+    let mut distinct_pieces = Vec::new();
+    for &piece in pieces {
+        if !distinct_pieces.contains(&piece) {
+            distinct_pieces.push(piece);
+        }
+    }
+    distinct_pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Color::*;
+    use crate::common::Size::*;
+
+    fn piece(size: Size, color: Color) -> Piece {
+        Piece { size, color }
+    }
+
+    // Builds a game already in progress with the given systems, drawing their pieces from the bank.
+    fn game_in_turn(player: PlayerIndex, systems: Vec<System>) -> Game {
+        multiplayer_game_in_turn(MIN_PLAYERS, player, systems)
+    }
+
+    fn multiplayer_game_in_turn(num_players: u8, player: PlayerIndex, systems: Vec<System>) -> Game {
+        let mut game = Game::new(RuleSet { num_players, ..RuleSet::default() });
+        for system in systems {
+            let mut pieces = system.stars();
+            for player in 0..num_players {
+                pieces.extend(system.get_ships(player));
+            }
+            game.bank.remove_several(&pieces).unwrap();
+            game.add_system(system);
+        }
+        game.state = State::Turn(player, TurnPhase::Started);
+        game
+    }
+
+    fn homeworld(stars: [Piece; 2], player: PlayerIndex, ships: &[(PlayerIndex, Piece)]) -> System {
+        let mut system = System::new_homeworld(stars, player);
+        for &(owner, ship) in ships {
+            system.add_ship(owner, ship);
+        }
+        system
+    }
+
+    fn colony(star: Piece, ships: &[(PlayerIndex, Piece)]) -> System {
+        let mut system = System::new(star);
+        for &(owner, ship) in ships {
+            system.add_ship(owner, ship);
+        }
+        system
+    }
+
+    // Player 0's only ship at home is a small green, guarded from evaporation by an enemy ship.
+    // Player 0 also has a large yellow to sacrifice elsewhere.
+    fn guarded_home_game() -> Game {
+        game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0,
+                      &[(0, piece(SMALL, GREEN)), (1, piece(SMALL, RED))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+            colony(piece(LARGE, RED), &[(0, piece(LARGE, YELLOW)), (0, piece(MEDIUM, GREEN))]),
+        ])
+    }
+
+    fn yellow(system: SystemId, ship: Piece, target: YellowActionInput) -> Action {
+        Action { system, ship, color_action: ColorAction::YellowAction(target) }
+    }
+
+    #[test]
+    fn system_ids_survive_other_systems_evaporating() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, GREEN))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+            colony(piece(MEDIUM, YELLOW), &[(0, piece(SMALL, GREEN))]),
+            colony(piece(LARGE, RED), &[(0, piece(MEDIUM, GREEN))]),
+        ]);
+        // Moving the only ship out of system 2 evaporates it.
+        game.free_move(2, YELLOW).unwrap();
+        game.action(yellow(2, piece(SMALL, GREEN), YellowActionInput::Existing(3))).unwrap();
+        assert_eq!(game.systems.keys().copied().collect::<Vec<SystemId>>(), vec![0, 1, 3]);
+        assert!(game.systems[&3].has_ship(0, piece(SMALL, GREEN)));
+        assert!(game.systems[&3].has_ship(0, piece(MEDIUM, GREEN)));
+    }
+
+    #[test]
+    fn leaving_home_and_returning_within_a_sacrifice_is_not_a_loss() {
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Existing(2))).unwrap();
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Sacrifice(YELLOW, 2)));
+        game.action(yellow(2, piece(SMALL, GREEN), YellowActionInput::Existing(0))).unwrap();
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+    }
+
+    #[test]
+    fn leaving_home_through_a_discovered_system_and_returning_is_not_a_loss() {
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Discover(piece(LARGE, BLUE)))).unwrap();
+        game.action(yellow(3, piece(SMALL, GREEN), YellowActionInput::Existing(0))).unwrap();
+        // The discovered system emptied out again, but its ID is not recycled.
+        assert!(!game.systems.contains_key(&3));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+    }
+
+    #[test]
+    fn staying_away_from_home_loses_at_end_of_turn() {
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Existing(2))).unwrap();
+        game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Discover(piece(SMALL, GREEN)))).unwrap();
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Sacrifice(YELLOW, 1)));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(1));
+    }
+
+    #[test]
+    fn forfeiting_remaining_sacrifice_actions_still_evaluates_losses() {
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Existing(2))).unwrap();
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(1));
+    }
+
+    #[test]
+    fn evaporating_own_home_mid_turn_loses_at_end_of_turn() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(SMALL, GREEN))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+        ]);
+        game.free_move(0, YELLOW).unwrap();
+        game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Discover(piece(LARGE, RED)))).unwrap();
+        assert!(!game.systems.contains_key(&0));
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Done));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(1));
+    }
+
+    #[test]
+    fn capturing_the_last_defender_wins_only_at_end_of_turn() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, RED), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, GREEN))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1,
+                      &[(0, piece(MEDIUM, RED)), (1, piece(SMALL, GREEN))]),
+        ]);
+        game.free_move(1, RED).unwrap();
+        game.action(Action {
+            system: 1,
+            ship: piece(MEDIUM, RED),
+            color_action: ColorAction::RedAction(RedActionInput { enemy_player: 1, ship_to_take: piece(SMALL, GREEN) }),
+        }).unwrap();
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Done));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(0));
+    }
+
+    #[test]
+    fn catastrophe_fired_after_the_last_action_is_evaluated_at_end_of_turn() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, GREEN), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, GREEN))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1,
+                      &[(0, piece(MEDIUM, BLUE)), (1, piece(SMALL, YELLOW)), (1, piece(MEDIUM, YELLOW))]),
+        ]);
+        game.free_move(1, BLUE).unwrap();
+        game.action(Action { system: 1, ship: piece(MEDIUM, BLUE), color_action: ColorAction::BlueAction(YELLOW) }).unwrap();
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Done));
+        // Yellow star, two yellow ships of player 1's and the freshly traded one of player 0's.
+        game.catastrophe(1, YELLOW).unwrap();
+        assert!(!game.systems.contains_key(&1));
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Done));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(0));
+    }
+
+    // Both homeworlds die to catastrophes during player 0's turn.
+    fn mutual_destruction_game(mutual_destruction: MutualDestruction) -> Game {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0,
+                      &[(0, piece(MEDIUM, YELLOW)), (0, piece(LARGE, YELLOW)), (1, piece(SMALL, YELLOW))]),
+            homeworld([piece(LARGE, GREEN), piece(SMALL, BLUE)], 1,
+                      &[(1, piece(MEDIUM, GREEN)), (1, piece(LARGE, GREEN)), (0, piece(SMALL, GREEN))]),
+            colony(piece(LARGE, RED), &[(0, piece(MEDIUM, RED))]),
+        ]);
+        game.rules.mutual_destruction = mutual_destruction;
+        game.sacrifice(2, piece(MEDIUM, RED)).unwrap();
+        game.catastrophe(0, YELLOW).unwrap();
+        game.catastrophe(1, GREEN).unwrap();
+        game
+    }
+
+    #[test]
+    fn destroying_both_homeworlds_is_a_draw() {
+        let mut game = mutual_destruction_game(MutualDestruction::Draw);
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Sacrifice(RED, 2)));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Drawn);
+        assert_eq!(game.catastrophe(0, YELLOW), Err(InputError::WrongState));
+    }
+
+    #[test]
+    fn destroying_both_homeworlds_can_lose_for_the_mover() {
+        let mut game = mutual_destruction_game(MutualDestruction::MoverLoses);
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Finished(1));
+    }
+
+    // Like the two-player case, but with players 2 and up looking on from homeworlds of their own.
+    fn multiplayer_mutual_destruction_game(num_players: u8, mutual_destruction: MutualDestruction) -> Game {
+        let mut systems = vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0,
+                      &[(0, piece(MEDIUM, YELLOW)), (0, piece(LARGE, YELLOW)), (1, piece(SMALL, YELLOW))]),
+            homeworld([piece(LARGE, GREEN), piece(SMALL, BLUE)], 1,
+                      &[(1, piece(MEDIUM, GREEN)), (1, piece(LARGE, GREEN)), (0, piece(SMALL, GREEN))]),
+            colony(piece(LARGE, RED), &[(0, piece(MEDIUM, RED))]),
+        ];
+        for player in 2..num_players {
+            systems.push(homeworld([piece(SMALL, RED), piece(LARGE, BLUE)], player, &[(player, piece(MEDIUM, BLUE))]));
+        }
+        let mut game = multiplayer_game_in_turn(num_players, 0, systems);
+        game.rules.mutual_destruction = mutual_destruction;
+        game.sacrifice(2, piece(MEDIUM, RED)).unwrap();
+        game.catastrophe(0, YELLOW).unwrap();
+        game.catastrophe(1, GREEN).unwrap();
+        game
+    }
+
+    #[test]
+    fn destroying_both_homeworlds_leaves_the_rest_playing() {
+        for &mutual_destruction in [MutualDestruction::Draw, MutualDestruction::MoverLoses].iter() {
+            let mut game = multiplayer_mutual_destruction_game(3, mutual_destruction);
+            let outcome = game.end_turn().unwrap();
+            assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(0), GameEvent::PlayerEliminated(1), GameEvent::GameWon(2)]);
+            assert_eq!(game.state(), State::Finished(2));
+
+            let mut game = multiplayer_mutual_destruction_game(4, mutual_destruction);
+            let outcome = game.end_turn().unwrap();
+            assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(0), GameEvent::PlayerEliminated(1)]);
+            assert_eq!(game.state(), State::Turn(2, TurnPhase::Started));
+            assert_eq!(game.systems.keys().copied().collect::<Vec<SystemId>>(), vec![3, 4]);
+        }
+    }
+
+    fn assert_rejected<T: fmt::Debug + PartialEq>(game: &mut Game, expected: InputError,
+                                                  input: impl FnOnce(&mut Game) -> Result<T, InputError>) {
+        let before = game.clone();
+        assert_eq!(input(game), Err(expected));
+        assert_eq!(*game, before);
+    }
+
+    fn drain(game: &mut Game, piece: Piece) {
+        while game.bank.num_available(piece) > 0 {
+            game.bank.remove(piece).unwrap();
+        }
+    }
+
+    fn skirmish_game() -> Game {
+        game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0,
+                      &[(0, piece(SMALL, GREEN)), (0, piece(LARGE, RED)), (1, piece(SMALL, RED)), (1, piece(MEDIUM, YELLOW))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN)), (1, piece(MEDIUM, BLUE))]),
+            colony(piece(MEDIUM, GREEN), &[(0, piece(LARGE, YELLOW)), (1, piece(MEDIUM, YELLOW))]),
+        ])
+    }
+
+    fn red(system: SystemId, ship: Piece, enemy_player: PlayerIndex, ship_to_take: Piece) -> Action {
+        Action { system, ship, color_action: ColorAction::RedAction(RedActionInput { enemy_player, ship_to_take }) }
+    }
+
+    #[test]
+    fn board_rendering_is_sorted() {
+        let game = skirmish_game();
+        assert_eq!(game.to_string(), "\
+Bank: Red 2S 3M 2L | Blue 2S 1M 3L | Green 2S 2M 2L | Yellow 2S 1M 1L
+Player 0's turn, no move selected
+Systems:
+0: Player 0's homeworld, binary stars Small Yellow/Medium Blue; Player 0's ship(s): Small Green, Large Red; Player 1's ship(s): Small Red, Medium Yellow; 
+1: Player 1's homeworld, binary stars Small Blue/Large Yellow; Player 1's ship(s): Medium Blue, Large Green; 
+2: Medium Green star; Player 0's ship(s): Large Yellow; Player 1's ship(s): Medium Yellow; 
+");
+    }
+
+    #[test]
+    fn board_rendering_ignores_arrival_order() {
+        let game = game_in_turn(0, vec![
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 0,
+                      &[(1, piece(MEDIUM, RED)), (0, piece(LARGE, GREEN)), (1, piece(SMALL, RED)), (0, piece(SMALL, YELLOW)), (0, piece(SMALL, GREEN))]),
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+        ]);
+        let rendering = game.to_string();
+        assert!(rendering.contains("0: Player 0's homeworld, binary stars Small Blue/Large Yellow; \
+                                    Player 0's ship(s): Small Green, Small Yellow, Large Green; \
+                                    Player 1's ship(s): Small Red, Medium Red; \n"), "{}", rendering);
+    }
+
+    #[test]
+    fn rejected_setup_inputs_change_nothing() {
+        let mut game = Game::new(RuleSet::default());
+        assert_rejected(&mut game, InputError::WrongState, |game| game.free_move(0, RED));
+        assert_rejected(&mut game, InputError::WrongState, |game| game.sacrifice(0, piece(LARGE, RED)));
+        assert_rejected(&mut game, InputError::WrongState, |game| game.catastrophe(0, RED));
+        assert_rejected(&mut game, InputError::WrongState, |game| game.end_turn());
+        assert_rejected(&mut game, InputError::WrongState,
+                        |game| game.action(yellow(0, piece(LARGE, RED), YellowActionInput::Existing(0))));
+        game.setup(&SetupMove { stars: [piece(LARGE, GREEN), piece(LARGE, GREEN)], ship: piece(LARGE, GREEN) }).unwrap();
+        assert_rejected(&mut game, InputError::PieceUnavailable { piece: piece(LARGE, GREEN), available: 0 },
+                        |game| game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) }));
+    }
+
+    #[test]
+    fn rejected_turn_start_inputs_change_nothing() {
+        let mut game = skirmish_game();
+        let setup_move = SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) };
+        assert_rejected(&mut game, InputError::WrongState, |game| game.setup(&setup_move));
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.end_turn());
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.action(red(0, piece(LARGE, RED), 1, piece(SMALL, RED))));
+        assert_rejected(&mut game, InputError::BadSystem(99), |game| game.free_move(99, RED));
+        assert_rejected(&mut game, InputError::FreeActionUnavailable { system: 1, player: 0, color: BLUE }, |game| game.free_move(1, BLUE));
+        assert_rejected(&mut game, InputError::FreeActionUnavailable { system: 2, player: 0, color: RED }, |game| game.free_move(2, RED));
+        assert_rejected(&mut game, InputError::BadSystem(99), |game| game.sacrifice(99, piece(LARGE, RED)));
+        assert_rejected(&mut game, InputError::NoSuchShip { system: 0, player: 0, ship: piece(SMALL, RED) }, |game| game.sacrifice(0, piece(SMALL, RED)));
+        assert_rejected(&mut game, InputError::BadSystem(99), |game| game.catastrophe(99, RED));
+        assert_rejected(&mut game, InputError::NotCatastrophicEnough { system: 0, color: RED, count: 2, needed: 4 }, |game| game.catastrophe(0, RED));
+    }
+
+    #[test]
+    fn rejected_red_actions_change_nothing() {
+        let mut game = skirmish_game();
+        game.free_move(0, RED).unwrap();
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.free_move(0, RED));
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.sacrifice(0, piece(LARGE, RED)));
+        assert_rejected(&mut game, InputError::WrongSystem { expected: 0, actual: 2 }, |game| game.action(red(2, piece(LARGE, YELLOW), 1, piece(MEDIUM, YELLOW))));
+        assert_rejected(&mut game, InputError::WrongActionColor { expected: RED, actual: GREEN },
+                        |game| game.action(Action { system: 0, ship: piece(SMALL, GREEN), color_action: ColorAction::GreenAction }));
+        assert_rejected(&mut game, InputError::NoSuchShip { system: 0, player: 0, ship: piece(MEDIUM, RED) }, |game| game.action(red(0, piece(MEDIUM, RED), 1, piece(SMALL, RED))));
+        assert_rejected(&mut game, InputError::WrongPlayer { enemy_player: 0, target: 1 }, |game| game.action(red(0, piece(LARGE, RED), 0, piece(SMALL, GREEN))));
+        assert_rejected(&mut game, InputError::WrongPlayer { enemy_player: 7, target: 1 }, |game| game.action(red(0, piece(LARGE, RED), 7, piece(SMALL, RED))));
+        assert_rejected(&mut game, InputError::ShipTooBig { ship: piece(SMALL, GREEN), ship_to_take: piece(MEDIUM, YELLOW) }, |game| game.action(red(0, piece(SMALL, GREEN), 1, piece(MEDIUM, YELLOW))));
+        assert_rejected(&mut game, InputError::NoSuchShip { system: 0, player: 1, ship: piece(LARGE, GREEN) }, |game| game.action(red(0, piece(LARGE, RED), 1, piece(LARGE, GREEN))));
+    }
+
+    #[test]
+    fn rejected_blue_and_green_actions_change_nothing() {
+        let mut game = skirmish_game();
+        game.free_move(0, BLUE).unwrap();
+        let trade = |new_color| Action { system: 0, ship: piece(SMALL, GREEN), color_action: ColorAction::BlueAction(new_color) };
+        assert_rejected(&mut game, InputError::WrongColor(GREEN), |game| game.action(trade(GREEN)));
+        drain(&mut game, piece(SMALL, YELLOW));
+        assert_rejected(&mut game, InputError::PieceUnavailable { piece: piece(SMALL, YELLOW), available: 0 }, |game| game.action(trade(YELLOW)));
+
+        let mut game = skirmish_game();
+        game.free_move(0, GREEN).unwrap();
+        for &size in ALL_SIZES.iter() {
+            drain(&mut game, piece(size, GREEN));
+        }
+        assert_rejected(&mut game, InputError::ColorUnavailable(GREEN),
+                        |game| game.action(Action { system: 0, ship: piece(SMALL, GREEN), color_action: ColorAction::GreenAction }));
+    }
+
+    #[test]
+    fn rejected_yellow_actions_change_nothing() {
+        let mut game = skirmish_game();
+        game.free_move(0, YELLOW).unwrap();
+        let ship = piece(SMALL, GREEN);
+        assert_rejected(&mut game, InputError::BadSystem(99), |game| game.action(yellow(0, ship, YellowActionInput::Existing(99))));
+        assert_rejected(&mut game, InputError::SystemsNotAdjacent { from: 0, to: 1 }, |game| game.action(yellow(0, ship, YellowActionInput::Existing(1))));
+        assert_rejected(&mut game, InputError::StarNotAdjacent { from: 0, star: piece(SMALL, RED) },
+                        |game| game.action(yellow(0, ship, YellowActionInput::Discover(piece(SMALL, RED)))));
+        drain(&mut game, piece(LARGE, BLUE));
+        assert_rejected(&mut game, InputError::PieceUnavailable { piece: piece(LARGE, BLUE), available: 0 },
+                        |game| game.action(yellow(0, ship, YellowActionInput::Discover(piece(LARGE, BLUE)))));
+    }
+
+    #[test]
+    fn rejected_sacrifice_actions_change_nothing() {
+        let mut game = skirmish_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.sacrifice(0, piece(LARGE, RED)));
+        assert_rejected(&mut game, InputError::WrongActionColor { expected: YELLOW, actual: RED }, |game| game.action(red(0, piece(LARGE, RED), 1, piece(SMALL, RED))));
+        assert_rejected(&mut game, InputError::NoSuchShip { system: 2, player: 0, ship: piece(LARGE, YELLOW) },
+                        |game| game.action(yellow(2, piece(LARGE, YELLOW), YellowActionInput::Existing(1))));
+        assert_rejected(&mut game, InputError::BadSystem(99),
+                        |game| game.action(yellow(99, piece(SMALL, GREEN), YellowActionInput::Existing(0))));
+    }
+
+    #[test]
+    fn rejected_inputs_after_the_game_change_nothing() {
+        let mut game = mutual_destruction_game(MutualDestruction::Draw);
+        game.end_turn().unwrap();
+        assert_rejected(&mut game, InputError::WrongState, |game| game.free_move(0, RED));
+        assert_rejected(&mut game, InputError::WrongState, |game| game.sacrifice(0, piece(LARGE, RED)));
+        assert_rejected(&mut game, InputError::WrongState, |game| game.end_turn());
+    }
+
+    #[test]
+    fn outcomes_report_what_happened() {
+        let mut game = guarded_home_game();
+        let outcome = game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        assert_eq!(outcome.events, vec![GameEvent::ShipSacrificed { system: 2, player: 0, ship: piece(LARGE, YELLOW) }]);
+        let outcome = game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Discover(piece(LARGE, BLUE)))).unwrap();
+        assert_eq!(outcome.events, vec![
+            GameEvent::SystemDiscovered { system: 3, star: piece(LARGE, BLUE) },
+            GameEvent::ShipMoved { player: 0, ship: piece(SMALL, GREEN), from: 0, to: 3 },
+        ]);
+        let outcome = game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Discover(piece(SMALL, GREEN)))).unwrap();
+        assert_eq!(outcome.events, vec![
+            GameEvent::SystemDiscovered { system: 4, star: piece(SMALL, GREEN) },
+            GameEvent::ShipMoved { player: 0, ship: piece(MEDIUM, GREEN), from: 2, to: 4 },
+            GameEvent::SystemEvaporated(2),
+        ]);
+        let outcome = game.end_turn().unwrap();
+        assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(0), GameEvent::GameWon(1)]);
+    }
+
+    #[test]
+    fn catastrophe_outcomes_list_destroyed_pieces() {
+        let mut game_with_survivor = game_in_turn(0, vec![
+            colony(piece(LARGE, RED), &[(0, piece(SMALL, RED)), (1, piece(MEDIUM, RED)), (1, piece(LARGE, RED)), (1, piece(LARGE, GREEN))]),
+        ]);
+        let outcome = game_with_survivor.catastrophe(0, RED).unwrap();
+        assert_eq!(outcome.events, vec![
+            GameEvent::ShipDestroyed { system: 0, player: 0, ship: piece(SMALL, RED) },
+            GameEvent::ShipDestroyed { system: 0, player: 1, ship: piece(MEDIUM, RED) },
+            GameEvent::ShipDestroyed { system: 0, player: 1, ship: piece(LARGE, RED) },
+            GameEvent::StarDestroyed { system: 0, star: piece(LARGE, RED) },
+            GameEvent::SystemEvaporated(0),
+        ]);
+        let mut game = mutual_destruction_game(MutualDestruction::Draw);
+        let outcome = game.end_turn().unwrap();
+        assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(0), GameEvent::PlayerEliminated(1), GameEvent::GameDrawn]);
+    }
+
+    #[test]
+    fn observers_hear_every_event_until_removed() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut game = guarded_home_game();
+        let observer_heard = Rc::clone(&heard);
+        let id = game.add_observer(Box::new(move |event: &GameEvent| observer_heard.borrow_mut().push(event.clone())));
+
+        let mut outcome = game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        outcome.events.extend(game.action(yellow(0, piece(SMALL, GREEN), YellowActionInput::Existing(2))).unwrap().events);
+        // Rejected inputs don't fire anything.
+        assert!(game.action(yellow(2, piece(SMALL, GREEN), YellowActionInput::Existing(1))).is_err());
+        assert_eq!(*heard.borrow(), outcome.events);
+
+        // Clones don't inherit observers.
+        game.clone().end_turn().unwrap();
+        assert_eq!(heard.borrow().len(), outcome.events.len());
+
+        assert!(game.remove_observer(id));
+        assert!(!game.remove_observer(id));
+        game.end_turn().unwrap();
+        assert_eq!(heard.borrow().len(), outcome.events.len());
+    }
+
+    #[test]
+    fn turns_apply_whole_or_not_at_all() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut game = skirmish_game();
+        let observer_heard = Rc::clone(&heard);
+        game.add_observer(Box::new(move |event: &GameEvent| observer_heard.borrow_mut().push(event.clone())));
+
+        let before = game.clone();
+        let turn = Turn { inputs: vec![
+            Input::Sacrifice(0, piece(LARGE, RED)),
+            Input::Action(red(0, piece(SMALL, GREEN), 1, piece(SMALL, RED))),
+            Input::Action(red(0, piece(SMALL, GREEN), 1, piece(MEDIUM, YELLOW))),
+            Input::EndTurn,
+        ] };
+        assert_eq!(game.apply_turn(&turn),
+                   Err(TurnError { index: 2, error: InputError::ShipTooBig { ship: piece(SMALL, GREEN), ship_to_take: piece(MEDIUM, YELLOW) } }));
+        assert_eq!(game, before);
+        assert!(heard.borrow().is_empty());
+
+        let turn = Turn { inputs: vec![turn.inputs[0], turn.inputs[1], Input::EndTurn] };
+        let outcomes = game.apply_turn(&turn).unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(*heard.borrow(), outcomes.into_iter().flat_map(|outcome| outcome.events).collect::<Vec<GameEvent>>());
+        assert_eq!(game.inputs(), turn.inputs);
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+    }
+
+    #[test]
+    fn turns_run_from_start_to_end() {
+        let mut game = skirmish_game();
+        let before = game.clone();
+        let sacrifice = Input::Sacrifice(0, piece(LARGE, RED));
+        let capture = Input::Action(red(0, piece(SMALL, GREEN), 1, piece(SMALL, RED)));
+        assert_eq!(game.apply_turn(&Turn { inputs: vec![sacrifice, capture] }),
+                   Err(TurnError { index: 1, error: InputError::TurnUnfinished }));
+        assert_eq!(game.apply_turn(&Turn { inputs: vec![] }), Err(TurnError { index: 0, error: InputError::TurnUnfinished }));
+        assert_eq!(game.apply_turn(&Turn { inputs: vec![sacrifice, Input::EndTurn, Input::FreeMove(1, GREEN)] }),
+                   Err(TurnError { index: 2, error: InputError::TurnOver }));
+        assert_eq!(game, before);
+
+        game.apply(sacrifice).unwrap();
+        let before = game.clone();
+        assert_eq!(game.apply_turn(&Turn { inputs: vec![capture, Input::EndTurn] }),
+                   Err(TurnError { index: 0, error: InputError::WrongPhase }));
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn undo_restores_every_earlier_position_and_redo_replays_them() {
+        let mut game = Game::new(RuleSet::default());
+        let inputs = [
+            Input::Setup(SetupMove { stars: [piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) }),
+            Input::Setup(SetupMove { stars: [piece(LARGE, YELLOW), piece(SMALL, BLUE)], ship: piece(LARGE, GREEN) }),
+            Input::FreeMove(0, YELLOW),
+            Input::Action(yellow(0, piece(LARGE, GREEN), YellowActionInput::Discover(piece(LARGE, RED)))),
+            Input::EndTurn,
+        ];
+        let mut positions = vec![game.snapshot()];
+        for &input in inputs.iter() {
+            game.apply(input).unwrap();
+            positions.push(game.snapshot());
+        }
+        assert_eq!(game.state(), State::Finished(1));
+
+        for (index, &input) in inputs.iter().enumerate().rev() {
+            assert_eq!(game.undo(), Some(input));
+            assert_eq!(game.snapshot(), positions[index]);
+        }
+        assert_eq!(game.undo(), None);
+        assert_eq!(game, Game { redo_stack: inputs.iter().rev().copied().collect(), ..Game::new(RuleSet::default()) });
+
+        for position in positions.iter().skip(1) {
+            assert!(game.redo().is_some());
+            assert_eq!(game.snapshot(), *position);
+        }
+        assert!(game.redo().is_none());
+    }
+
+    #[test]
+    fn undo_restores_evaporated_systems_and_ids() {
+        let mut game = guarded_home_game();
+        let before = game.snapshot();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Discover(piece(SMALL, GREEN)))).unwrap();
+        assert!(!game.systems.contains_key(&2));
+        game.undo();
+        game.undo();
+        assert_eq!(game.snapshot(), before);
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        let outcome = game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Discover(piece(SMALL, GREEN)))).unwrap();
+        assert_eq!(outcome.events[0], GameEvent::SystemDiscovered { system: 3, star: piece(SMALL, GREEN) });
+    }
+
+    #[test]
+    fn new_inputs_discard_undone_ones() {
+        let mut game = guarded_home_game();
+        game.free_move(0, YELLOW).unwrap();
+        game.undo();
+        assert!(game.apply(Input::FreeMove(99, YELLOW)).is_err());
+        // A rejected input doesn't count as a new one.
+        assert!(game.redo().is_some());
+        game.undo();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        assert!(game.redo().is_none());
+    }
+
+    // Far more inputs than could ever be legal, including references to systems, players and
+    // pieces that don't exist.
+    fn input_space(game: &Game) -> Vec<Input> {
+        let systems: Vec<SystemId> = (0..game.next_system_id + 2).collect();
+        let players: Vec<PlayerIndex> = (0..game.rules.num_players + 1).collect();
+        let mut color_actions = vec![ColorAction::GreenAction];
+        for &color in ALL_COLORS.iter() {
+            color_actions.push(ColorAction::BlueAction(color));
+        }
+        for &system in systems.iter() {
+            color_actions.push(ColorAction::YellowAction(YellowActionInput::Existing(system)));
+        }
+        for piece in all_pieces() {
+            color_actions.push(ColorAction::YellowAction(YellowActionInput::Discover(piece)));
+            for &enemy_player in players.iter() {
+                color_actions.push(ColorAction::RedAction(RedActionInput { enemy_player, ship_to_take: piece }));
+            }
+        }
+        let mut inputs = vec![Input::EndTurn];
+        for &system in systems.iter() {
+            for &color in ALL_COLORS.iter() {
+                inputs.push(Input::FreeMove(system, color));
+                inputs.push(Input::Catastrophe(system, color));
+            }
+            for ship in all_pieces() {
+                inputs.push(Input::Sacrifice(system, ship));
+                for &color_action in color_actions.iter() {
+                    inputs.push(Input::Action(Action { system, ship, color_action }));
+                }
+            }
+        }
+        inputs
+    }
+
+    fn assert_legal_inputs_match_validators(game: &Game) {
+        let legal_inputs = game.legal_inputs();
+        for input in input_space(game) {
+            let mut after = game.clone();
+            let result = after.apply(input);
+            assert_eq!(result.is_ok(), legal_inputs.contains(&input), "{:?} in {:?}", input, game.state());
+            assert_eq!(game.check(&input), result.map(|_| ()));
+        }
+        for input in legal_inputs.iter() {
+            assert!(game.clone().apply(*input).is_ok(), "{:?} in {:?}", input, game.state());
+        }
+    }
+
+    #[test]
+    fn every_setup_is_legal_from_a_full_bank() {
+        let game = Game::new(RuleSet::default());
+        assert_eq!(game.legal_inputs().len(), 12 * 12 * 12);
+        assert!(game.legal_inputs().iter().all(|input| matches!(input, Input::Setup(_))));
+    }
+
+    #[test]
+    fn legal_setups_respect_the_bank() {
+        let mut game = Game::new(RuleSet::default());
+        game.setup(&SetupMove { stars: [piece(LARGE, GREEN), piece(LARGE, GREEN)], ship: piece(LARGE, GREEN) }).unwrap();
+        let legal_inputs = game.legal_inputs();
+        assert_eq!(legal_inputs.len(), 11 * 11 * 11);
+        for input in legal_inputs {
+            assert!(game.clone().apply(input).is_ok());
+        }
+    }
+
+    #[test]
+    fn legal_inputs_match_validators_in_every_phase() {
+        let game = skirmish_game();
+        assert_legal_inputs_match_validators(&game);
+        for &color in ALL_COLORS.iter() {
+            let mut free_move_game = game.clone();
+            if free_move_game.free_move(0, color).is_ok() {
+                assert_legal_inputs_match_validators(&free_move_game);
+            }
+        }
+        for &(system, ship) in [(2, piece(LARGE, YELLOW)), (0, piece(LARGE, RED)), (0, piece(SMALL, GREEN))].iter() {
+            let mut sacrifice_game = game.clone();
+            sacrifice_game.sacrifice(system, ship).unwrap();
+            assert_legal_inputs_match_validators(&sacrifice_game);
+        }
+
+        let mut done_game = mutual_destruction_game(MutualDestruction::Draw);
+        assert_legal_inputs_match_validators(&done_game);
+        done_game.end_turn().unwrap();
+        assert_legal_inputs_match_validators(&done_game);
+        assert!(done_game.legal_inputs().is_empty());
+    }
+
+    #[test]
+    fn free_actions_that_cant_be_taken_are_forfeited() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, RED))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+        ]);
+        // There's nothing to capture at home.
+        game.free_move(0, RED).unwrap();
+        assert_eq!(game.legal_inputs(), vec![Input::EndTurn]);
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+
+        let mut game = abandoned_home_game();
+        game.free_move(3, YELLOW).unwrap();
+        assert_rejected(&mut game, InputError::WrongPhase, |game| game.end_turn());
+        game.catastrophe(3, RED).unwrap();
+        assert_legal_inputs_match_validators(&game);
+        assert!(game.legal_inputs().contains(&Input::EndTurn));
+        game.end_turn().unwrap();
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+    }
+
+    #[test]
+    fn legal_inputs_include_every_discoverable_star() {
+        let mut game = skirmish_game();
+        game.free_move(0, YELLOW).unwrap();
+        let discoveries: Vec<Piece> = game.legal_inputs().iter().filter_map(|input| match input {
+            Input::Action(Action { ship, color_action: ColorAction::YellowAction(YellowActionInput::Discover(star)), .. })
+                if *ship == piece(SMALL, GREEN) => Some(*star),
+            _ => None,
+        }).collect();
+        // Only large stars are adjacent to the small/medium homeworld; one large yellow is on the board.
+        assert_eq!(discoveries, vec![piece(LARGE, RED), piece(LARGE, BLUE), piece(LARGE, GREEN), piece(LARGE, YELLOW)]);
+    }
+
+    fn opening_game() -> Game {
+        let mut game = Game::new(RuleSet::default());
+        game.setup(&SetupMove { stars: [piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) }).unwrap();
+        game.setup(&SetupMove { stars: [piece(LARGE, YELLOW), piece(SMALL, BLUE)], ship: piece(LARGE, GREEN) }).unwrap();
+        game
+    }
+
+    #[test]
+    fn perft_counts_stay_pinned() {
+        assert_eq!(Game::new(RuleSet::default()).perft(1), 936);
+        let game = opening_game();
+        assert_eq!(game.perft(0), 1);
+        assert_eq!(game.perft(1), 9);
+        assert_eq!(game.perft(2), 36);
+        assert_eq!(game.perft(3), 187);
+        assert_eq!(game.perft(4), 1508);
+        assert_eq!(skirmish_game().perft(1), 155);
+    }
+
+    #[test]
+    fn legal_turns_replay_to_distinct_positions() {
+        let game = skirmish_game();
+        let mut positions = HashSet::new();
+        for turn in game.legal_turns() {
+            let mut after = game.clone();
+            for &input in turn.inputs.iter() {
+                after.apply(input).unwrap();
+            }
+            assert_eq!(turn.inputs.last(), Some(&Input::EndTurn));
+            assert_ne!(after.state(), State::Turn(0, TurnPhase::Started));
+            assert!(positions.insert(after.position()));
+        }
+    }
+
+    #[test]
+    fn turns_reaching_the_same_position_count_once() {
+        // Moving the two ships out to the same new system in either order ends up the same.
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Existing(0))).unwrap();
+        let discover = |ship| Input::Action(yellow(0, ship, YellowActionInput::Discover(piece(LARGE, BLUE))));
+        let follow = |ship| Input::Action(yellow(0, ship, YellowActionInput::Existing(3)));
+        let small_first = Turn { inputs: vec![discover(piece(SMALL, GREEN)), follow(piece(MEDIUM, GREEN)), Input::EndTurn] };
+        let medium_first = Turn { inputs: vec![discover(piece(MEDIUM, GREEN)), follow(piece(SMALL, GREEN)), Input::EndTurn] };
+        let turns = game.legal_turns();
+        assert_eq!(turns.iter().filter(|&turn| *turn == small_first || *turn == medium_first).count(), 1);
+    }
+
+    #[test]
+    fn discovery_order_makes_no_difference() {
+        // The same two systems come out, just with each other's IDs.
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        game.action(yellow(2, piece(MEDIUM, GREEN), YellowActionInput::Existing(0))).unwrap();
+        let discover = |ship, star| Input::Action(yellow(0, ship, YellowActionInput::Discover(star)));
+        let blue_first = Turn { inputs: vec![discover(piece(SMALL, GREEN), piece(LARGE, BLUE)),
+                                             discover(piece(MEDIUM, GREEN), piece(LARGE, RED)), Input::EndTurn] };
+        let red_first = Turn { inputs: vec![discover(piece(MEDIUM, GREEN), piece(LARGE, RED)),
+                                            discover(piece(SMALL, GREEN), piece(LARGE, BLUE)), Input::EndTurn] };
+        let turns = game.legal_turns();
+        assert_eq!(turns.iter().filter(|&turn| *turn == blue_first || *turn == red_first).count(), 1);
+    }
+
+    #[test]
+    fn every_seat_sets_up_before_the_first_turn() {
+        let mut game = Game::new(RuleSet { num_players: 3, ..RuleSet::default() });
+        for player in 0..3 {
+            assert_eq!(game.state(), State::Setup(player));
+            game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) }).unwrap();
+        }
+        assert_eq!(game.state(), State::Turn(0, TurnPhase::Started));
+        assert_eq!(game.left_of(2), 0);
+    }
+
+    // Player 0 sits with player 1 on their left and player 2 on their right. Player 0 has ships
+    // in everyone's homeworld, and all three share a large red colony.
+    fn three_player_game() -> Game {
+        multiplayer_game_in_turn(3, 0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, RED))]),
+            homeworld([piece(SMALL, RED), piece(MEDIUM, YELLOW)], 1, &[(1, piece(SMALL, GREEN)), (0, piece(MEDIUM, RED))]),
+            homeworld([piece(SMALL, GREEN), piece(MEDIUM, RED)], 2,
+                      &[(2, piece(SMALL, GREEN)), (2, piece(MEDIUM, GREEN)), (0, piece(LARGE, GREEN)), (0, piece(LARGE, YELLOW))]),
+            colony(piece(LARGE, RED), &[(0, piece(LARGE, YELLOW)), (1, piece(SMALL, YELLOW)), (2, piece(SMALL, YELLOW))]),
+        ])
+    }
+
+    #[test]
+    fn red_actions_only_attack_the_player_to_the_left() {
+        let mut game = three_player_game();
+        game.free_move(3, RED).unwrap();
+        assert_rejected(&mut game, InputError::WrongPlayer { enemy_player: 2, target: 1 }, |game| game.action(red(3, piece(LARGE, YELLOW), 2, piece(SMALL, YELLOW))));
+        game.action(red(3, piece(LARGE, YELLOW), 1, piece(SMALL, YELLOW))).unwrap();
+        assert_eq!(game.systems[&3].get_ships(0), &[piece(LARGE, YELLOW), piece(SMALL, YELLOW)]);
+    }
+
+    #[test]
+    fn eliminating_the_player_to_the_left_wins() {
+        let mut game = three_player_game();
+        game.free_move(1, RED).unwrap();
+        game.action(red(1, piece(MEDIUM, RED), 1, piece(SMALL, GREEN))).unwrap();
+        let outcome = game.end_turn().unwrap();
+        assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(1), GameEvent::GameWon(0)]);
+        assert_eq!(game.state(), State::Finished(0));
+    }
+
+    #[test]
+    fn other_eliminated_players_leave_the_game() {
+        let mut game = three_player_game();
+        game.free_move(3, RED).unwrap();
+        game.action(red(3, piece(LARGE, YELLOW), 1, piece(SMALL, YELLOW))).unwrap();
+        game.catastrophe(2, GREEN).unwrap();
+        let outcome = game.end_turn().unwrap();
+        assert_eq!(outcome.events, vec![GameEvent::PlayerEliminated(2)]);
+        assert_eq!(game.state(), State::Turn(1, TurnPhase::Started));
+        assert!(game.is_eliminated(2));
+        // Their ships go back to the bank, and what's left of their homeworld is up for grabs.
+        assert!(game.systems[&3].get_ships(2).is_empty());
+        assert_eq!(game.bank.num_available(piece(SMALL, YELLOW)), 1);
+        assert_eq!(game.systems[&2].home_player, None);
+        // Play now skips them, and player 1's left neighbour is player 0.
+        assert_eq!(game.left_of(1), 0);
+        game.free_move(1, RED).unwrap();
+        assert_rejected(&mut game, InputError::WrongPlayer { enemy_player: 2, target: 0 }, |game| game.action(red(1, piece(SMALL, GREEN), 2, piece(SMALL, GREEN))));
+    }
+
+    #[test]
+    fn rules_restrict_setups_and_stock() {
+        let setup_rules = SetupRules { large_starting_ship: true, distinct_star_sizes: true };
+        let mut game = Game::new(RuleSet { stock: 1, setup: setup_rules, ..RuleSet::default() });
+        assert_rejected(&mut game, InputError::SetupNotAllowed(SetupRule::LargeStartingShip),
+                        |game| game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, BLUE)], ship: piece(MEDIUM, GREEN) }));
+        assert_rejected(&mut game, InputError::SetupNotAllowed(SetupRule::DistinctStarSizes),
+                        |game| game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(SMALL, BLUE)], ship: piece(LARGE, GREEN) }));
+        game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, BLUE)], ship: piece(LARGE, GREEN) }).unwrap();
+        assert_rejected(&mut game, InputError::PieceUnavailable { piece: piece(SMALL, RED), available: 0 },
+                        |game| game.setup(&SetupMove { stars: [piece(SMALL, RED), piece(MEDIUM, YELLOW)], ship: piece(LARGE, YELLOW) }));
+    }
+
+    #[test]
+    fn rules_set_the_catastrophe_threshold() {
+        let mut game = game_in_turn(0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, GREEN))]),
+            homeworld([piece(LARGE, YELLOW), piece(SMALL, BLUE)], 1, &[(1, piece(LARGE, GREEN))]),
+            colony(piece(LARGE, RED), &[(0, piece(LARGE, YELLOW)), (1, piece(MEDIUM, YELLOW)), (1, piece(SMALL, YELLOW))]),
+        ]);
+        assert_rejected(&mut game, InputError::NotCatastrophicEnough { system: 2, color: YELLOW, count: 3, needed: 4 }, |game| game.catastrophe(2, YELLOW));
+        game.rules.catastrophe_count = 3;
+        game.catastrophe(2, YELLOW).unwrap();
+        assert!(!game.systems.contains_key(&2));
+    }
+
+    #[test]
+    fn sacrificed_ships_go_back_to_the_bank() {
+        let mut game = guarded_home_game();
+        let available = game.bank.num_available(piece(LARGE, YELLOW));
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        assert_eq!(game.bank.num_available(piece(LARGE, YELLOW)), available + 1);
+        assert_eq!(game.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn invariant_checks_catch_broken_boards() {
+        let mut game = skirmish_game();
+        assert_eq!(game.check_invariants(), Ok(()));
+        game.bank.remove(piece(SMALL, YELLOW)).unwrap();
+        assert_eq!(game.check_invariants(), Err("There are 2 Small Yellow pieces instead of 3".into()));
+
+        let mut game = skirmish_game();
+        game.systems.get_mut(&1).unwrap().home_player = Some(0);
+        assert_eq!(game.check_invariants(), Err("Player 0 has more than one homeworld".into()));
+
+        let mut game = skirmish_game();
+        game.eliminated.push(1);
+        assert_eq!(game.check_invariants(), Err("System 1 is the homeworld of player 1, who isn't playing".into()));
+    }
+
+    #[test]
+    fn errors_describe_what_went_wrong() {
+        let mut game = skirmish_game();
+        assert_eq!(game.catastrophe(0, RED).unwrap_err().to_string(), "System 0 has 2 Red pieces, but a catastrophe needs 4");
+        game.free_move(0, RED).unwrap();
+        assert_eq!(game.action(red(0, piece(SMALL, GREEN), 1, piece(MEDIUM, YELLOW))).unwrap_err().to_string(),
+                   "A Small Green ship can't capture a bigger Medium Yellow ship");
+        assert_eq!(game.action(red(0, piece(LARGE, RED), 1, piece(LARGE, GREEN))).unwrap_err().to_string(),
+                   "Player 1 has no Large Green ship in system 0");
+        let mut bank = Bank::full(1);
+        assert_eq!(bank.remove_several(&[piece(SMALL, RED), piece(SMALL, RED)]).unwrap_err().to_string(),
+                   "The bank has only 1 Small Red left");
+    }
+
+    // Player 2 only has a small yellow at home, where yellow and red catastrophes are waiting.
+    fn abandoned_home_game() -> Game {
+        multiplayer_game_in_turn(3, 0, vec![
+            homeworld([piece(SMALL, YELLOW), piece(MEDIUM, BLUE)], 0, &[(0, piece(LARGE, GREEN))]),
+            homeworld([piece(SMALL, RED), piece(MEDIUM, GREEN)], 1, &[(1, piece(LARGE, GREEN))]),
+            homeworld([piece(SMALL, GREEN), piece(MEDIUM, RED)], 2,
+                      &[(2, piece(SMALL, YELLOW)), (0, piece(LARGE, YELLOW)), (0, piece(MEDIUM, YELLOW)), (1, piece(SMALL, YELLOW)), (0, piece(LARGE, BLUE))]),
+            colony(piece(LARGE, RED), &[(0, piece(MEDIUM, YELLOW)), (0, piece(MEDIUM, RED)), (1, piece(SMALL, RED)), (2, piece(SMALL, RED))]),
+        ])
+    }
+
+    #[test]
+    fn notation_round_trips_exactly() {
+        let mut games = vec![Game::new(RuleSet::default()), opening_game(), skirmish_game()];
+        let mut game = skirmish_game();
+        game.free_move(0, YELLOW).unwrap();
+        games.push(game);
+        let mut game = guarded_home_game();
+        game.sacrifice(2, piece(LARGE, YELLOW)).unwrap();
+        games.push(game);
+        let mut game = three_player_game();
+        game.catastrophe(2, GREEN).unwrap();
+        games.push(game);
+        let mut game = mutual_destruction_game(MutualDestruction::Draw);
+        game.end_turn().unwrap();
+        games.push(game);
+        // Player 2's homeworld outlives them, and the free action's system goes before it's used.
+        let mut game = abandoned_home_game();
+        game.catastrophe(2, YELLOW).unwrap();
+        game.free_move(0, GREEN).unwrap();
+        game.action(Action { system: 0, ship: piece(LARGE, GREEN), color_action: ColorAction::GreenAction }).unwrap();
+        game.end_turn().unwrap();
+        assert_eq!(game.systems[&2].home_player, None);
+        games.push(game);
+        let mut game = abandoned_home_game();
+        game.free_move(3, YELLOW).unwrap();
+        game.catastrophe(3, RED).unwrap();
+        assert!(!game.systems.contains_key(&3));
+        games.push(game);
+        for game in games {
+            let notation = game.to_notation();
+            let parsed = Game::from_notation(&notation, game.rules).unwrap();
+            assert_eq!(parsed.snapshot(), game.snapshot());
+            assert_eq!(parsed.to_notation(), notation);
+        }
+    }
+
+    const SKIRMISH_NOTATION: &str =
+        "turn:0:started bank:2,3,2,2,1,3,2,2,2,2,1,1 out:- next:3 0:sy,mb@0/0:sg,lr/1:sr,my 1:ly,sb@1/1:lg,mb 2:mg/0:ly/1:my";
+
+    #[test]
+    fn notation_lists_the_whole_position() {
+        assert_eq!(skirmish_game().to_notation(), SKIRMISH_NOTATION);
+        assert_eq!(opening_game().to_notation(),
+                   "turn:0:started bank:3,3,3,2,2,3,3,3,1,2,3,2 out:- next:2 0:sy,mb@0/0:lg 1:ly,sb@1/1:lg");
+    }
+
+    #[test]
+    fn notation_rejects_inconsistent_positions() {
+        let parse = |notation: &str| Game::from_notation(notation, RuleSet::default()).map(|_| ()).unwrap_err();
+        assert_eq!(parse("turn:0:started"), "The position has no bank: field");
+        assert_eq!(Game::from_notation(SKIRMISH_NOTATION, RuleSet { num_players: 5, ..RuleSet::default() }).map(|_| ()).unwrap_err(),
+                   "The game needs 2 to 4 players");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("turn:0:started", "turn:0:sac:r:4")), "A sacrifice gives 1 to 3 actions, not 4");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("bank:2,", "bank:")), "The bank lists 11 pieces instead of 12");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("bank:2,", "bank:3,")), "There are 4 Small Red pieces instead of 3");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("next:3", "next:2")), "System 2 can't exist when the next system ID is 2");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("ly,sb@1", "ly,sb,lr@1")), "System 1 has the wrong number of stars");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("/1:my", "/2:my")), "There is no player 2 in a 2 player game");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("2:mg/0:ly/1:my", "2:mg/0:ly/0:my")), "Player 0's ships are listed twice in system 2");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("out:-", "out:0")), "It can't be player 0's turn, they've been eliminated");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("turn:0:started", "setup:1")),
+                   "Only the homeworlds of players before player 1 can exist during their setup");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("sg,lr", "xg,lr")), "Not recognized as a size: x");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("ly,sb@1", "ly,sb")),
+                   "System 1 has two stars but isn't a homeworld, and nobody has been eliminated");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("out:-", "out:1").replace("sy,mb@0", "sy,mb").replace("ly,sb@1", "ly,sb")),
+                   "System 1 has two stars but isn't a homeworld, and every eliminated player's homeworld is accounted for");
+        assert_eq!(parse(&SKIRMISH_NOTATION.replace("turn:0:started", "turn:0:free:3:y")),
+                   "The free action is in system 3, which can't exist when the next system ID is 3");
+        assert_eq!(parse("setup:0 bank:3,3,3,3,3,3,3,3,3,3,3,3 out:- next:5"), "The next system ID during player 0's setup is 0, not 5");
+    }
+}
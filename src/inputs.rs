@@ -1,6 +1,7 @@
+use std::fmt;
 use crate::common::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SetupMove {
     pub stars: [Piece; 2],
     pub ship: Piece,
@@ -9,18 +10,30 @@ pub struct SetupMove {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TurnPhase {
     Started,
-    FreeMove(SystemIndex, Color),
+    FreeMove(SystemId, Color),
     Sacrifice(Color, u8), // number of remaining moves available
     Done,
 }
 
+impl fmt::Display for TurnPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TurnPhase::Started => write!(f, "no move selected"),
+            TurnPhase::Done => write!(f, "no moves left"),
+            TurnPhase::FreeMove(system, color) => write!(f, "free {} move in system {}", color, system),
+            TurnPhase::Sacrifice(color, moves_left) => write!(f, "{} sacrifice, {} move(s) left", color, moves_left),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Action {
-    pub system: SystemIndex,
+    pub system: SystemId,
     pub ship: Piece,
     pub color_action: ColorAction,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ColorAction {
     RedAction(RedActionInput),
@@ -29,6 +42,17 @@ pub enum ColorAction {
     YellowAction(YellowActionInput),
 }
 
+impl ColorAction {
+    pub fn color(&self) -> Color {
+        match self {
+            ColorAction::RedAction(_) => Color::RED,
+            ColorAction::BlueAction(_) => Color::BLUE,
+            ColorAction::GreenAction => Color::GREEN,
+            ColorAction::YellowAction(_) => Color::YELLOW,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RedActionInput {
     pub enemy_player: PlayerIndex,
@@ -37,7 +61,24 @@ pub struct RedActionInput {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum YellowActionInput {
-    Existing(SystemIndex),
+    Existing(SystemId),
     Discover(Piece),
 }
 
+/// Anything a player can ask the game to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Setup(SetupMove),
+    FreeMove(SystemId, Color),
+    Sacrifice(SystemId, Piece),
+    Action(Action),
+    Catastrophe(SystemId, Color),
+    EndTurn,
+}
+
+/// Everything one player does from the start of their turn until it passes to the next player.
+/// During setup that's just the one setup move.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Turn {
+    pub inputs: Vec<Input>,
+}
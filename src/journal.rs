@@ -0,0 +1,208 @@
+//! Journals: game records written as the game is played, so that a game survives the program
+//! dying halfway through.
+//!
+//! A journal is a record (see [`crate::record`]) whose header leaves out the result and the
+//! position, since those keep changing, and which is only ever appended to. An input that's
+//! taken back is journaled as an `undo` line rather than erased. The file is only created once
+//! there's an input to put in it. Once its game is over, or the player chooses not to resume it,
+//! a journal is closed: renamed so that it's kept but never offered or replayed again.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::game::*;
+use crate::inputs::*;
+use crate::notation::*;
+use crate::record::*;
+
+const EXTENSION: &str = "journal";
+const CLOSED_EXTENSION: &str = "closed";
+
+/// A journal file, if it's been created yet, and the inputs it says were played so far.
+pub struct Journal {
+    path: PathBuf,
+    file: Option<File>,
+    inputs: Vec<Input>,
+}
+
+impl Journal {
+    /// A new journal in the directory. Nothing is written until the first sync with an input to
+    /// journal, which writes every input played in the game so far, creating the directory if
+    /// need be.
+    pub fn new(directory: &Path) -> Journal {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+        let path = directory.join(format!("game-{}-{}.{}", seconds, std::process::id(), EXTENSION));
+        Journal { path, file: None, inputs: Vec::new() }
+    }
+
+    /// Carries on with an existing journal, which must hold the game as it is now, e.g. because
+    /// the game was just loaded from it.
+    pub fn resume(path: &Path, game: &Game) -> io::Result<Journal> {
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Journal { path: path.to_path_buf(), file: Some(file), inputs: game.inputs() })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends whatever the game played or took back since the journal last heard of it.
+    pub fn sync(&mut self, game: &Game) -> io::Result<()> {
+        let inputs = game.inputs();
+        let kept = self.inputs.iter().zip(inputs.iter()).take_while(|(journaled, played)| journaled == played).count();
+        let mut lines = String::new();
+        for _ in kept..self.inputs.len() {
+            lines.push_str("undo\n");
+        }
+        for input in inputs[kept..].iter() {
+            lines.push_str(&input_notation(input));
+            lines.push('\n');
+        }
+        if !lines.is_empty() {
+            let file = match self.file.as_mut() {
+                Some(file) => file,
+                None => self.file.insert(Journal::create_file(&self.path, game)?),
+            };
+            file.write_all(lines.as_bytes())?;
+            file.flush()?;
+        }
+        self.inputs = inputs;
+        Ok(())
+    }
+
+    /// Closes the journal, if anything was written to it, so that it's never offered again.
+    pub fn close(self) -> io::Result<()> {
+        match self.file {
+            None => Ok(()),
+            Some(file) => {
+                drop(file);
+                close_journal(&self.path)
+            },
+        }
+    }
+
+    fn create_file(path: &Path, game: &Game) -> io::Result<File> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        write!(file, "# Homeworlds game journal\nPlayers: {}\nDate: {}\nRules: {}\n\n",
+               game.rules().num_players, current_date(), game.rules())?;
+        Ok(file)
+    }
+}
+
+/// Renames the journal to end in `.closed`, so that `latest_unfinished` passes it over.
+pub fn close_journal(path: &Path) -> io::Result<()> {
+    fs::rename(path, path.with_extension(CLOSED_EXTENSION))
+}
+
+/// The most recently changed journal in the directory whose game isn't over yet, replayed.
+/// Journals that can't be read or replayed, or that have no inputs, are passed over.
+pub fn latest_unfinished(directory: &Path) -> Option<(PathBuf, RecordHeader, Game)> {
+    let mut journals: Vec<(SystemTime, PathBuf)> = fs::read_dir(directory).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == EXTENSION))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    journals.sort();
+    journals.into_iter().rev().find_map(|(_, path)| {
+        let (header, game) = load_record(&fs::read_to_string(&path).ok()?).ok()?;
+        match game.state() {
+            State::Finished(_) | State::Drawn => None,
+            _ if game.inputs().is_empty() => None,
+            _ => Some((path, header, game)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::*;
+
+    fn empty_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("homeworlds-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn journals_replay_to_the_game_as_it_was() {
+        let directory = empty_directory("journal");
+        let mut game = Game::new(RuleSet::default());
+        let mut journal = Journal::new(&directory);
+        journal.sync(&game).unwrap();
+        assert!(!directory.exists());
+        for input in ["setup sy mb lg", "setup ly sb lg", "free b 0", "blue lg 0 y"].iter() {
+            game.apply(parse_input(input).unwrap()).unwrap();
+            journal.sync(&game).unwrap();
+        }
+        game.undo();
+        game.undo();
+        game.apply(parse_input("free g 0").unwrap()).unwrap();
+        journal.sync(&game).unwrap();
+
+        let (path, _, resumed) = latest_unfinished(&directory).unwrap();
+        assert_eq!(path, journal.path());
+        assert_eq!(resumed.to_notation(), game.to_notation());
+        assert_eq!(resumed.inputs(), game.inputs());
+
+        let mut journal = Journal::resume(&path, &resumed).unwrap();
+        game.apply(parse_input("green lg 0").unwrap()).unwrap();
+        journal.sync(&game).unwrap();
+        let (_, _, resumed) = latest_unfinished(&directory).unwrap();
+        assert_eq!(resumed.inputs(), game.inputs());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn finished_games_arent_resumed() {
+        let directory = empty_directory("finished");
+        let mut game = Game::new(RuleSet::default());
+        let mut journal = Journal::new(&directory);
+        for input in ["setup sy mb lg", "setup ly sb lg", "free y 0", "yellow lg 0 new lr", "end"].iter() {
+            game.apply(parse_input(input).unwrap()).unwrap();
+        }
+        journal.sync(&game).unwrap();
+        assert_eq!(game.state(), State::Finished(1));
+        assert!(latest_unfinished(&directory).is_none());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn journals_without_inputs_arent_resumed() {
+        let directory = empty_directory("empty");
+        let mut game = Game::new(RuleSet::default());
+        let mut journal = Journal::new(&directory);
+        game.apply(parse_input("setup sy mb lg").unwrap()).unwrap();
+        journal.sync(&game).unwrap();
+        game.undo();
+        journal.sync(&game).unwrap();
+        assert!(journal.path().exists());
+        assert!(latest_unfinished(&directory).is_none());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn closed_journals_arent_offered_again() {
+        let directory = empty_directory("closed");
+        let mut game = Game::new(RuleSet::default());
+        let mut journal = Journal::new(&directory);
+        game.apply(parse_input("setup sy mb lg").unwrap()).unwrap();
+        journal.sync(&game).unwrap();
+        let (path, _, _) = latest_unfinished(&directory).unwrap();
+        // Declining to resume it.
+        close_journal(&path).unwrap();
+        assert!(latest_unfinished(&directory).is_none());
+        assert!(path.with_extension(CLOSED_EXTENSION).exists());
+
+        let mut journal = Journal::new(&directory.join("finished"));
+        journal.sync(&game).unwrap();
+        journal.close().unwrap();
+        assert!(latest_unfinished(&directory.join("finished")).is_none());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
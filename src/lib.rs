@@ -0,0 +1,21 @@
+//! The rules engine for Homeworlds, the space strategy game played with pyramids of four colors
+//! and three sizes.
+//!
+//! A [`game::Game`] takes [`inputs::Input`]s one at a time, rejecting illegal ones with an
+//! [`common::InputError`] and leaving the game unchanged. Each accepted input returns an
+//! [`outcome::ActionOutcome`] listing the [`event::GameEvent`]s it caused, which observers can
+//! also listen for as they happen. The variant rules a game is played with are set by a
+//! [`rules::RuleSet`].
+
+pub mod common;
+pub mod bank;
+pub mod system;
+pub mod inputs;
+pub mod rules;
+pub mod event;
+pub mod outcome;
+pub mod notation;
+pub mod record;
+pub mod journal;
+pub mod render;
+pub mod game;
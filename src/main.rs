@@ -1,206 +1,439 @@
-mod common;
-mod bank;
-mod system;
-mod inputs;
-mod game;
-
-use std::io;
-use common::*;
-use inputs::*;
-use game::*;
-use crate::inputs::ColorAction::{GreenAction, YellowAction, BlueAction, RedAction};
-use crate::common::Color::*;
-use crate::common::Size::*;
-use std::str::SplitWhitespace;
-use crate::inputs::YellowActionInput::{Discover, Existing};
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use homeworlds::inputs::*;
+use homeworlds::game::*;
+use homeworlds::rules::*;
+use homeworlds::event::*;
+use homeworlds::notation::*;
+use homeworlds::common::*;
+use homeworlds::record::*;
+use homeworlds::journal::*;
+use homeworlds::render::*;
 
 fn main() {
-    let mut game = Game::new();
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error_message) => {
+            eprintln!("{}", error_message);
+            std::process::exit(2);
+        },
+    };
+    let mut game = Game::new(options.rules);
+    let view = View {
+        colored: options.color.unwrap_or_else(|| io::stdout().is_terminal()),
+        perspective: options.perspective,
+        names: options.names,
+    };
+    let result = match options.script {
+        None => {
+            let journal = options.journal.and_then(|directory| open_journal(&directory, &mut game));
+            game.add_observer(event_printer(&view));
+            println!("Rules: {}", game.rules());
+            play_interactively(&mut game, journal, &view)
+        },
+        Some(script) => {
+            if !options.final_only {
+                game.add_observer(event_printer(&view));
+                println!("Rules: {}", game.rules());
+            }
+            run_script(&script, &mut game, options.final_only, &view)
+        },
+    };
+    if let Err(error_message) = result {
+        eprintln!("{}", error_message);
+        std::process::exit(1);
+    }
+}
+
+struct Options {
+    rules: RuleSet,
+    script: Option<String>, // A file name, or - for stdin
+    final_only: bool,
+    journal: Option<PathBuf>, // The directory interactive games are journaled in
+    color: Option<bool>, // None to color the board only when printing to a terminal
+    perspective: bool,
+    names: Vec<String>, // By seat, or empty to call players by number
+}
+
+// How the board is shown.
+struct View {
+    colored: bool,
+    perspective: bool,
+    names: Vec<String>,
+}
+
+impl View {
+    // The perspective rendering isn't colored; --color=always is refused with --perspective.
+    fn board(&self, game: &Game) -> String {
+        if self.perspective {
+            render_perspective(game, &self.names)
+        } else if self.colored {
+            render_colored(game)
+        } else {
+            game.to_string()
+        }
+    }
+}
+
+// Every rule can be set with an option named after it, e.g. --players=3 or --stock=4. Besides
+// those, --script <file> runs the commands in the file instead of asking for them, and
+// --final-only makes a script print just the final position. Interactive games are journaled in
+// ~/.homeworlds/journal unless --journal=<directory> says elsewhere or --journal=off.
+// --color=always|never|auto says whether to draw the board in color; auto, the default, colors
+// it when printing to a terminal. --names=<name>,<name>,... names the players in seat order, and
+// --perspective shows the board, uncolored, as the player whose turn it is sees it.
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let journal = std::env::var_os("HOME").map(|home| Path::new(&home).join(".homeworlds").join("journal"));
+    let mut options = Options { rules: RuleSet::default(), script: None, final_only: false, journal, color: None,
+                               perspective: false, names: Vec::new() };
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--script" => match args.next() {
+                None => return Err("--script needs a file, or - for stdin".into()),
+                Some(script) => options.script = Some(script),
+            },
+            "--final-only" => options.final_only = true,
+            "--perspective" => options.perspective = true,
+            _ => {
+                let option = arg.strip_prefix("--").and_then(|option| option.split_once('='));
+                match option {
+                    None => return Err(format!("Unknown option: {}", arg)),
+                    Some(("script", script)) => options.script = Some(script.to_string()),
+                    Some(("journal", "off")) => options.journal = None,
+                    Some(("journal", directory)) => options.journal = Some(PathBuf::from(directory)),
+                    Some(("color", "always")) => options.color = Some(true),
+                    Some(("color", "never")) => options.color = Some(false),
+                    Some(("color", "auto")) => options.color = None,
+                    Some(("color", value)) => return Err(format!("Expected always, never or auto for color: {}", value)),
+                    Some(("names", names)) => options.names = names.split(',').map(|name| name.trim().to_string()).collect(),
+                    Some((name, value)) => options.rules.set(name, value)?,
+                }
+            },
+        }
+    }
+    if options.final_only && options.script.is_none() {
+        return Err("--final-only only applies to --script".into());
+    }
+    if options.perspective && options.color == Some(true) {
+        return Err("--color=always doesn't apply to --perspective, which is never colored".into());
+    }
+    options.rules.validate()?;
+    if !options.names.is_empty() {
+        if options.names.len() != options.rules.num_players as usize {
+            return Err(format!("Expected {} names, one for each player", options.rules.num_players));
+        }
+        if options.names.iter().any(|name| name.is_empty()) {
+            return Err("Names can't be empty".into());
+        }
+        if options.names.iter().enumerate().any(|(index, name)| options.names[..index].contains(name)) {
+            return Err("Each player needs a different name".into());
+        }
+    }
+    Ok(options)
+}
+
+// Prints each event as it happens, calling the players by name if they have one.
+fn event_printer(view: &View) -> Box<dyn GameObserver> {
+    let names = view.names.clone();
+    Box::new(move |event: &GameEvent| println!("{}", event.describe(|player| player_name(&names, player))))
+}
+
+// What a command did, as far as the main loop cares.
+enum Step {
+    Played,
+    Explained,
+    Quit,
+}
+
+fn execute(input: &str, game: &mut Game) -> Result<Step, String> {
+    let mut tokens = input.split_whitespace();
+    match tokens.next() {
+        None => Err("".into()),
+        Some("quit") => Ok(Step::Quit),
+        Some("help") => input_help(tokens.next()).map(|_| Step::Explained),
+        Some("map") => {
+            print!("{}", render_map(game));
+            Ok(Step::Explained)
+        },
+        Some("save") => input_save(tokens.next(), game).map(|_| Step::Explained),
+        Some("load") => input_load(tokens.next(), game).map(|_| Step::Played),
+        Some(_) if input.contains(';') => input_turn(input, game).map(|_| Step::Played),
+        Some("undo") => input_undo(game).map(|_| Step::Played),
+        Some("redo") => input_redo(game).map(|_| Step::Played),
+        Some(_) => input_move(input, game).map(|_| Step::Played),
+    }
+}
 
+// Offers to resume the latest unfinished game in the directory, if someone's at the terminal to
+// answer, then journals whichever game is played. A game that isn't resumed is closed, so it's not
+// offered again. Without a journal the game can still be played, just not recovered.
+fn open_journal(directory: &Path, game: &mut Game) -> Option<Journal> {
+    let unfinished = if io::stdin().is_terminal() { latest_unfinished(directory) } else { None };
+    if let Some((path, header, unfinished_game)) = unfinished {
+        println!("Found an unfinished game from {} in {}. Resume it? (y/n)",
+                 header.date.as_deref().unwrap_or("an unknown date"), path.display());
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer).is_ok() && ["y", "yes"].contains(&answer.trim()) {
+            game.replace(unfinished_game);
+            match Journal::resume(&path, game) {
+                Ok(journal) => return Some(journal),
+                Err(error) => {
+                    eprintln!("Can't carry on the journal in {}: {}", path.display(), error);
+                    return None;
+                },
+            }
+        }
+        if let Err(error) = close_journal(&path) {
+            eprintln!("Can't close the journal {}: {}", path.display(), error);
+        }
+    }
+    Some(Journal::new(directory))
+}
+
+// Stops when the game ends, the player quits or stdin runs out.
+fn play_interactively(game: &mut Game, mut journal: Option<Journal>, view: &View) -> Result<(), String> {
     let mut input = String::new();
-    let mut last_input_failed = false;
-    let mut finished = false;
+    let mut board_unchanged = false;
     loop {
-        if !last_input_failed {
-            println!("{}", game);
+        if !board_unchanged {
+            println!("{}", view.board(game));
+        }
+        board_unchanged = false;
+        if let State::Setup(player) = game.state() {
+            println!("{}, enter your setup: setup <star> <star> <ship>", player_name(&view.names, player));
+        } else if let State::Turn(player, _) = game.state() {
+            println!("{}, now what? {} (help <command> explains one)", player_name(&view.names, player),
+                     available_commands(game.state()).join(", "));
         }
-        last_input_failed = false;
-        print!("Now what?\n");
         input.clear();
-        io::stdin().read_line(&mut input).unwrap();
-        let mut tokens = input.split_whitespace();
-        let first_token = tokens.next();
-        let result = match first_token {
-            None => Err("".into()),
-            Some("quit") => {
-                finished = true;
-                Ok(())
-            },
-            Some("setup") => input_setup(tokens, &mut game),
-            Some("free") => input_free(tokens, &mut game),
-            Some("sac") => input_sacrifice(tokens, &mut game),
-            Some("catastrophe") => input_catastrophe(tokens, &mut game),
-            Some("end") => input_end(&mut game),
-            Some("red") => input_action(tokens, &mut game, parse_red_action, RED),
-            Some("green") => input_action(tokens, &mut game, parse_green_action, GREEN),
-            Some("blue") => input_action(tokens, &mut game, parse_blue_action, BLUE),
-            Some("yellow") => input_action(tokens, &mut game, parse_yellow_action, YELLOW),
-            Some(first_token) => {
-                Err(format!("Unknown input: {}", first_token))
-            },
-        };
-        match result {
+        match io::stdin().read_line(&mut input) {
+            Err(error) => return Err(format!("Can't read input: {}", error)),
+            Ok(0) => return Ok(()),
+            Ok(_) => {},
+        }
+        match execute(&input, game) {
             Err(error_message) => {
                 println!("{}", error_message);
-                last_input_failed = true;
+                board_unchanged = true;
             },
-            Ok(()) => {
-                if finished {
-                    break;
+            Ok(Step::Explained) => board_unchanged = true,
+            Ok(Step::Quit) => return Ok(()),
+            Ok(Step::Played) => {
+                if let Some(journal_to_sync) = journal.as_mut() {
+                    if let Err(error) = journal_to_sync.sync(game) {
+                        eprintln!("Can't write to the journal {}, so this game won't be journaled: {}",
+                                  journal_to_sync.path().display(), error);
+                        journal = None;
+                    }
                 }
-                if let State::Finished(winner) = game.state {
-                    println!("Player {} wins. Final board:\n{}", winner, game);
-                    break;
+                if print_ending(game, view) {
+                    if let Some(journal) = journal {
+                        let path = journal.path().to_path_buf();
+                        if let Err(error) = journal.close() {
+                            eprintln!("Can't close the journal {}: {}", path.display(), error);
+                        }
+                    }
+                    return Ok(());
                 }
             },
         }
     }
 }
 
-fn input_free(mut tokens: SplitWhitespace, game: &mut Game) -> Result<(), String> {
-    let color = parse_next_token_as(&mut tokens, parse_color, "color")?;
-    let system = parse_next_token_as(&mut tokens, parse_u8, "system ID")?;
-    let result = game.free_move(system, color);
-    if let Err(error) = result {
-        return Err(format!("Failed to pick a free action: {:?}", error));
+// Blank lines and lines starting with # are skipped. The first command that fails stops the
+// script; anything after the end of the game fails too.
+fn run_script(script: &str, game: &mut Game, final_only: bool, view: &View) -> Result<(), String> {
+    let commands = if script == "-" {
+        io::read_to_string(io::stdin()).map_err(|error| format!("Can't read stdin: {}", error))?
+    } else {
+        std::fs::read_to_string(script).map_err(|error| format!("Can't read {}: {}", script, error))?
+    };
+    for (index, line) in commands.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match execute(line, game) {
+            Err(error_message) => return Err(format!("Line {}: {}", index + 1, error_message)),
+            Ok(Step::Quit) => break,
+            Ok(Step::Explained) => {},
+            Ok(Step::Played) => {
+                // The ending is printed with the final board.
+                if !final_only && !matches!(game.state(), State::Finished(_) | State::Drawn) {
+                    println!("{}", view.board(game));
+                }
+            },
+        }
+    }
+    if !print_ending(game, view) && final_only {
+        println!("{}", view.board(game));
     }
     Ok(())
 }
 
-fn input_action(mut tokens: SplitWhitespace, game: &mut Game,
-                action_parser: fn(SplitWhitespace) -> Result<ColorAction, String>, color: Color) -> Result<(), String> {
-    let ship = parse_next_token_as(&mut tokens, parse_piece, "ship")?;
-    let system = parse_next_token_as(&mut tokens, parse_u8, "system ID")?;
-    let color_action = action_parser(tokens)?;
-    let result = game.action(Action { ship, system, color_action });
-    if let Err(error) = result {
-        return Err(format!("Failed to perform a {} action: {:?}", color, error));
+// Whether the game is over, in which case it says how it ended.
+fn print_ending(game: &Game, view: &View) -> bool {
+    match game.state() {
+        State::Finished(winner) => println!("{} wins. Final board:\n{}", player_name(&view.names, winner), view.board(game)),
+        State::Drawn => println!("The game is a draw. Final board:\n{}", view.board(game)),
+        _ => return false,
     }
-    Ok(())
+    true
 }
 
-fn parse_green_action(mut tokens: SplitWhitespace) -> Result<ColorAction, String> {
-    Ok(GreenAction)
+fn input_move(input: &str, game: &mut Game) -> Result<(), String> {
+    let input = parse_input(input)?;
+    let failure = match input {
+        Input::Setup(_) => "Setup attempt failed".to_string(),
+        Input::FreeMove(_, _) => "Failed to pick a free action".to_string(),
+        Input::Sacrifice(_, _) => "Failed to sacrifice".to_string(),
+        Input::Action(action) => format!("Failed to perform a {} action", action.color_action.color()),
+        Input::Catastrophe(_, _) => "Catastrophe failed".to_string(),
+        Input::EndTurn => "Failed to end turn".to_string(),
+    };
+    match game.apply(input) {
+        Err(error) => Err(format!("{}: {}", failure, error)),
+        Ok(_) => Ok(()),
+    }
 }
 
-fn parse_blue_action(mut tokens: SplitWhitespace) -> Result<ColorAction, String> {
-    let new_color = parse_next_token_as(&mut tokens, parse_color, "new color")?;
-    Ok(BlueAction(new_color))
+struct CommandHelp {
+    name: &'static str,
+    syntax: &'static str,
+    description: &'static str,
+    example: &'static str,
 }
 
-fn parse_red_action(mut tokens: SplitWhitespace) -> Result<ColorAction, String> {
-    let enemy_ship = parse_next_token_as(&mut tokens, parse_piece, "enemy ship")?;
-    let enemy_player = parse_next_token_as(&mut tokens, parse_u8, "enemy player ID")?;
-    Ok(RedAction(RedActionInput { enemy_player, ship_to_take: enemy_ship }))
-}
+const COMMANDS: [CommandHelp; 16] = [
+    CommandHelp { name: "setup", syntax: "setup <star> <star> <ship>",
+        description: "Create your homeworld from two stars and your first ship",
+        example: "setup sy mb lg" },
+    CommandHelp { name: "free", syntax: "free <color> <system>",
+        description: "Start your turn with a free action of a color available to you in a system",
+        example: "free y 0" },
+    CommandHelp { name: "sac", syntax: "sac <system> <ship>",
+        description: "Start your turn by sacrificing a ship, for one to three actions of its color, by its size",
+        example: "sac 0 ly" },
+    CommandHelp { name: "red", syntax: "red <ship> <system> <enemy ship> <enemy player>",
+        description: "Capture an enemy ship no bigger than yours",
+        example: "red lr 0 sr 1" },
+    CommandHelp { name: "green", syntax: "green <ship> <system>",
+        description: "Build a ship of the same color as yours, the smallest the bank has",
+        example: "green sg 0" },
+    CommandHelp { name: "blue", syntax: "blue <ship> <system> <new color>",
+        description: "Trade a ship for one of the same size in another color",
+        example: "blue sg 0 y" },
+    CommandHelp { name: "yellow", syntax: "yellow <ship> <system> new <star> | yellow <ship> <system> old <system>",
+        description: "Move a ship to a newly discovered system or to an existing one",
+        example: "yellow sg 0 new lb" },
+    CommandHelp { name: "catastrophe", syntax: "catastrophe <system> <color>",
+        description: "Destroy every piece of a color in a system that has enough of it",
+        example: "catastrophe 0 y" },
+    CommandHelp { name: "end", syntax: "end",
+        description: "End your turn",
+        example: "end" },
+    CommandHelp { name: "undo", syntax: "undo",
+        description: "Take back the last input",
+        example: "undo" },
+    CommandHelp { name: "redo", syntax: "redo",
+        description: "Replay the last input taken back",
+        example: "redo" },
+    CommandHelp { name: "map", syntax: "map",
+        description: "Show every system with the systems next to it and the stars that could be discovered from it",
+        example: "map" },
+    CommandHelp { name: "save", syntax: "save <file>",
+        description: "Write the game so far to a file, to load later",
+        example: "save game.txt" },
+    CommandHelp { name: "load", syntax: "load <file>",
+        description: "Replace this game with a saved one played under the same rules",
+        example: "load game.txt" },
+    CommandHelp { name: "help", syntax: "help [command]",
+        description: "List the commands, or explain one",
+        example: "help yellow" },
+    CommandHelp { name: "quit", syntax: "quit",
+        description: "Leave the game",
+        example: "quit" },
+];
 
-fn parse_yellow_action(mut tokens: SplitWhitespace) -> Result<ColorAction, String> {
-    let first_token = tokens.next();
-    match first_token {
-        None => Err("No yellow action input".into()),
-        Some("new") => {
-            let new_star = parse_next_token_as(&mut tokens, parse_piece, "new star")?;
-            Ok(YellowAction(Discover(new_star)))
-        },
-        Some("old") => {
-            let system = parse_next_token_as(&mut tokens, parse_u8, "system ID")?;
-            Ok(YellowAction(Existing(system)))
-        },
-        Some(input) => Err(format!("Unknown yellow action {}", input))
-    }
+// The commands that can be accepted in the given state. Whether they're legal still depends on
+// the board.
+fn available_commands(state: State) -> Vec<&'static str> {
+    let mut commands = match state {
+        State::Setup(_) => vec!["setup"],
+        State::Turn(_, TurnPhase::Started) => vec!["free", "sac", "catastrophe"],
+        State::Turn(_, TurnPhase::FreeMove(_, color)) => vec![action_command(color), "catastrophe", "end"],
+        State::Turn(_, TurnPhase::Sacrifice(color, _)) => vec![action_command(color), "catastrophe", "end"],
+        State::Turn(_, TurnPhase::Done) => vec!["catastrophe", "end"],
+        State::Finished(_) | State::Drawn => vec![],
+    };
+    commands.extend(&["map", "undo", "redo", "save", "load", "help", "quit"]);
+    commands
 }
 
-fn input_catastrophe(mut tokens: SplitWhitespace, game: &mut Game) -> Result<(), String> {
-    let system = parse_next_token_as(&mut tokens, parse_u8, "system ID")?;
-    let color = parse_next_token_as(&mut tokens, parse_color, "color")?;
-    let result = game.catastrophe(system, color);
-    if let Err(error) = result {
-        return Err(format!("Catastrophe failed: {:?}", error));
+fn action_command(color: Color) -> &'static str {
+    match color {
+        Color::RED => "red",
+        Color::GREEN => "green",
+        Color::BLUE => "blue",
+        Color::YELLOW => "yellow",
     }
-    Ok(())
 }
 
-fn input_sacrifice(mut tokens: SplitWhitespace, game: &mut Game) -> Result<(), String> {
-    let system = parse_next_token_as(&mut tokens, parse_u8, "system ID")?;
-    let ship = parse_next_token_as(&mut tokens, parse_piece, "ship")?;
-    let result = game.sacrifice(system, ship);
-    if let Err(error) = result {
-        return Err(format!("Failed to sacrifice: {:?}", error));
+fn input_help(command: Option<&str>) -> Result<(), String> {
+    match command {
+        None => {
+            for command in COMMANDS.iter() {
+                println!("{:<11} {}", command.name, command.description);
+            }
+            println!("Pieces are a size (s, m, l) then a color (r, g, b, y), e.g. lg for a large green");
+            println!("A whole turn can be played at once, its inputs separated by ;, e.g. sac 0 lr; red sg 0 sr 1; end");
+            println!("Type help <command> for its syntax");
+        },
+        Some(name) => match COMMANDS.iter().find(|command| command.name == name) {
+            None => return Err(format!("No help for {}; type help to list the commands", name)),
+            Some(command) => println!("{}\n{}\nFor example: {}", command.syntax, command.description, command.example),
+        },
     }
     Ok(())
 }
 
-fn input_setup(mut tokens: SplitWhitespace, game: &mut Game) -> Result<(), String> {
-    let star1 = parse_next_token_as(&mut tokens, parse_piece, "star 1")?;
-    let star2 = parse_next_token_as(&mut tokens, parse_piece, "star 2")?;
-    let ship = parse_next_token_as(&mut tokens, parse_piece, "starting ship")?;
-    let result = game.setup(&SetupMove { ship, stars: [star1, star2] });
-    match result {
-        Err(error) => Err(format!("Setup attempt failed: {:?}", error)),
-        Ok(()) => Ok(()),
-    }
-}
-
-fn input_end(game: &mut Game) -> Result<(), String> {
-    let result = game.end_turn();
-    match result {
-        Err(error) => Err(format!("Failed to end turn: {:?}", error)),
-        Ok(()) => Ok(()),
+fn input_turn(input: &str, game: &mut Game) -> Result<(), String> {
+    let turn = parse_turn(input)?;
+    match game.apply_turn(&turn) {
+        Err(TurnError { index, error }) => {
+            let clause = input.split(';').filter(|clause| !clause.trim().is_empty()).nth(index).unwrap_or("").trim();
+            Err(format!("Clause {} ({}) failed, so none of the turn was played: {}", index + 1, clause, error))
+        },
+        Ok(_) => Ok(()),
     }
 }
 
-fn parse_next_token_as<T>(tokens: &mut SplitWhitespace, parse: fn(&str) -> Result<T, String>, description: &str) -> Result<T, String> {
-    let piece_input = tokens.next();
-    match piece_input {
-        None => Err(format!("Malformed input, {} not specified", description)),
-        Some(piece_input) => parse(piece_input),
-    }
+fn input_save(file: Option<&str>, game: &Game) -> Result<(), String> {
+    let file = file.ok_or("Malformed input, file not specified")?;
+    std::fs::write(file, write_record(game, &current_date())).map_err(|error| format!("Can't save to {}: {}", file, error))?;
+    println!("Saved to {}", file);
+    Ok(())
 }
 
-fn parse_piece(string: &str) -> Result<Piece, String> {
-    if string.len() != 2 {
-        return Err(format!("Unrecognized as a piece: {}", string));
-    }
-    let size_char = string.get(0..1).unwrap();
-    let color_char = string.get(1..2).unwrap();
-    let size = parse_size(size_char)?;
-    let color = parse_color(color_char)?;
-    Ok(Piece { size, color })
+fn input_load(file: Option<&str>, game: &mut Game) -> Result<(), String> {
+    let file = file.ok_or("Malformed input, file not specified")?;
+    let record = std::fs::read_to_string(file).map_err(|error| format!("Can't read {}: {}", file, error))?;
+    let (_, loaded_game) = load_record_with_rules(&record, game.rules()).map_err(|error| format!("Can't load {}: {}", file, error))?;
+    game.replace(loaded_game);
+    Ok(())
 }
 
-fn parse_size(string: &str) -> Result<Size, String> {
-     match string {
-        "s" => Ok(SMALL),
-        "m" => Ok(MEDIUM),
-        "l" => Ok(LARGE),
-        _ => Err(format!("Not recognized as a size: {}", string)),
+fn input_undo(game: &mut Game) -> Result<(), String> {
+    match game.undo() {
+        None => Err("Nothing to undo".into()),
+        Some(_) => Ok(()),
     }
 }
 
-fn parse_color(string: &str) -> Result<Color, String> {
-    match string {
-        "r" => Ok(RED),
-        "g" => Ok(GREEN),
-        "b" => Ok(BLUE),
-        "y" => Ok(YELLOW),
-        _ => Err(format!("Not recognized as a color: {}", string)),
+fn input_redo(game: &mut Game) -> Result<(), String> {
+    match game.redo() {
+        None => Err("Nothing to redo".into()),
+        Some(_) => Ok(()),
     }
 }
-
-fn parse_u8(string: &str) -> Result<u8, String> {
-    let parse_result = string.parse::<u8>();
-    match parse_result {
-        Err(error) => Err(format!("{} is not a number: {:?}", string, error)),
-        Ok(system) => Ok(system),
-    }
-}
\ No newline at end of file
@@ -0,0 +1,300 @@
+//! Short text forms for pieces, states and positions, shared by the CLI and the position
+//! notation of `Game::to_notation`.
+//!
+//! A piece is its size then its color, e.g. `lg` for a large green. An input is written the way
+//! it's typed into the CLI, e.g. `yellow lg 0 new sb`, and a turn is its inputs separated by
+//! semicolons, e.g. `sac 0 lr; red sg 0 sr 1; end`. A position is a single line
+//! of space separated fields:
+//!
+//! * the state: `setup:<player>`, `turn:<player>:<phase>`, `won:<player>` or `drawn`, where the
+//!   phase is `started`, `free:<system>:<color>`, `sac:<color>:<actions left>` or `done`
+//! * `bank:` and how many of each piece the bank holds, red then blue, green and yellow, each
+//!   small to large, separated by commas
+//! * `out:` and the eliminated players in the order they went out, or `-` if there are none
+//! * `next:` and the ID the next discovered system will get
+//! * each system: its ID, a colon and its stars, then `@<player>` if it's a homeworld, then
+//!   `/<player>:<ships>` for each player with ships there. An eliminated player's homeworld
+//!   keeps both its stars but loses the `@<player>`
+//!
+//! For example, `turn:0:started bank:3,3,3,2,2,3,3,3,1,2,3,2 out:- next:2 0:sy,mb@0/0:lg 1:ly,sb@1/1:lg`.
+
+use std::str::{FromStr, SplitWhitespace};
+use crate::common::*;
+use crate::inputs::*;
+use crate::game::State;
+
+pub fn color_notation(color: Color) -> char {
+    match color {
+        Color::RED => 'r',
+        Color::GREEN => 'g',
+        Color::BLUE => 'b',
+        Color::YELLOW => 'y',
+    }
+}
+
+pub fn size_notation(size: Size) -> char {
+    match size {
+        Size::SMALL => 's',
+        Size::MEDIUM => 'm',
+        Size::LARGE => 'l',
+    }
+}
+
+pub fn piece_notation(piece: Piece) -> String {
+    format!("{}{}", size_notation(piece.size), color_notation(piece.color))
+}
+
+/// Comma separated, e.g. `sy,mb`.
+pub fn pieces_notation(pieces: &[Piece]) -> String {
+    pieces.iter().map(|&piece| piece_notation(piece)).collect::<Vec<String>>().join(",")
+}
+
+pub fn parse_color(string: &str) -> Result<Color, String> {
+    match string {
+        "r" => Ok(Color::RED),
+        "g" => Ok(Color::GREEN),
+        "b" => Ok(Color::BLUE),
+        "y" => Ok(Color::YELLOW),
+        _ => Err(format!("Not recognized as a color: {}", string)),
+    }
+}
+
+pub fn parse_size(string: &str) -> Result<Size, String> {
+    match string {
+        "s" => Ok(Size::SMALL),
+        "m" => Ok(Size::MEDIUM),
+        "l" => Ok(Size::LARGE),
+        _ => Err(format!("Not recognized as a size: {}", string)),
+    }
+}
+
+pub fn parse_piece(string: &str) -> Result<Piece, String> {
+    if string.len() != 2 || !string.is_char_boundary(1) {
+        return Err(format!("Unrecognized as a piece: {}", string));
+    }
+    let size = parse_size(&string[0..1])?;
+    let color = parse_color(&string[1..2])?;
+    Ok(Piece { size, color })
+}
+
+pub fn parse_pieces(string: &str) -> Result<Vec<Piece>, String> {
+    string.split(',').map(parse_piece).collect()
+}
+
+pub fn parse_number<T: FromStr>(string: &str, description: &str) -> Result<T, String> {
+    string.parse().map_err(|_| format!("Not a valid {}: {}", description, string))
+}
+
+pub fn state_notation(state: State) -> String {
+    match state {
+        State::Setup(player) => format!("setup:{}", player),
+        State::Turn(player, phase) => {
+            let phase = match phase {
+                TurnPhase::Started => "started".to_string(),
+                TurnPhase::FreeMove(system, color) => format!("free:{}:{}", system, color_notation(color)),
+                TurnPhase::Sacrifice(color, actions_left) => format!("sac:{}:{}", color_notation(color), actions_left),
+                TurnPhase::Done => "done".to_string(),
+            };
+            format!("turn:{}:{}", player, phase)
+        },
+        State::Finished(winner) => format!("won:{}", winner),
+        State::Drawn => "drawn".to_string(),
+    }
+}
+
+pub fn parse_state(string: &str) -> Result<State, String> {
+    let parts: Vec<&str> = string.split(':').collect();
+    let state = match parts.as_slice() {
+        ["setup", player] => State::Setup(parse_number(player, "player")?),
+        ["turn", player, phase @ ..] => {
+            let phase = match phase {
+                ["started"] => TurnPhase::Started,
+                ["free", system, color] => TurnPhase::FreeMove(parse_number(system, "system ID")?, parse_color(color)?),
+                ["sac", color, actions_left] => {
+                    let actions_left = parse_number(actions_left, "number of sacrifice actions")?;
+                    if !(1..=3).contains(&actions_left) {
+                        return Err(format!("A sacrifice gives 1 to 3 actions, not {}", actions_left));
+                    }
+                    TurnPhase::Sacrifice(parse_color(color)?, actions_left)
+                },
+                ["done"] => TurnPhase::Done,
+                _ => return Err(format!("Not recognized as a turn phase: {}", phase.join(":"))),
+            };
+            State::Turn(parse_number(player, "player")?, phase)
+        },
+        ["won", winner] => State::Finished(parse_number(winner, "player")?),
+        ["drawn"] => State::Drawn,
+        _ => return Err(format!("Not recognized as a game state: {}", string)),
+    };
+    Ok(state)
+}
+
+pub fn input_notation(input: &Input) -> String {
+    match *input {
+        Input::Setup(SetupMove { stars, ship }) =>
+            format!("setup {} {} {}", piece_notation(stars[0]), piece_notation(stars[1]), piece_notation(ship)),
+        Input::FreeMove(system, color) => format!("free {} {}", color_notation(color), system),
+        Input::Sacrifice(system, ship) => format!("sac {} {}", system, piece_notation(ship)),
+        Input::Catastrophe(system, color) => format!("catastrophe {} {}", system, color_notation(color)),
+        Input::EndTurn => "end".to_string(),
+        Input::Action(Action { system, ship, color_action }) => {
+            let action = format!("{} {} {}", color_action.color().to_string().to_lowercase(), piece_notation(ship), system);
+            match color_action {
+                ColorAction::RedAction(RedActionInput { enemy_player, ship_to_take }) =>
+                    format!("{} {} {}", action, piece_notation(ship_to_take), enemy_player),
+                ColorAction::BlueAction(new_color) => format!("{} {}", action, color_notation(new_color)),
+                ColorAction::GreenAction => action,
+                ColorAction::YellowAction(YellowActionInput::Discover(new_star)) =>
+                    format!("{} new {}", action, piece_notation(new_star)),
+                ColorAction::YellowAction(YellowActionInput::Existing(existing_system)) =>
+                    format!("{} old {}", action, existing_system),
+            }
+        },
+    }
+}
+
+pub fn parse_input(string: &str) -> Result<Input, String> {
+    let mut tokens = string.split_whitespace();
+    let input = match tokens.next() {
+        None => return Err("No input".into()),
+        Some("setup") => {
+            let star1 = parse_next_token_as(&mut tokens, parse_piece, "star 1")?;
+            let star2 = parse_next_token_as(&mut tokens, parse_piece, "star 2")?;
+            let ship = parse_next_token_as(&mut tokens, parse_piece, "starting ship")?;
+            Input::Setup(SetupMove { ship, stars: [star1, star2] })
+        },
+        Some("free") => {
+            let color = parse_next_token_as(&mut tokens, parse_color, "color")?;
+            let system = parse_next_token_as(&mut tokens, parse_system_id, "system ID")?;
+            Input::FreeMove(system, color)
+        },
+        Some("sac") => {
+            let system = parse_next_token_as(&mut tokens, parse_system_id, "system ID")?;
+            let ship = parse_next_token_as(&mut tokens, parse_piece, "ship")?;
+            Input::Sacrifice(system, ship)
+        },
+        Some("catastrophe") => {
+            let system = parse_next_token_as(&mut tokens, parse_system_id, "system ID")?;
+            let color = parse_next_token_as(&mut tokens, parse_color, "color")?;
+            Input::Catastrophe(system, color)
+        },
+        Some("end") => Input::EndTurn,
+        Some("red") => parse_action(&mut tokens, parse_red_action)?,
+        Some("green") => parse_action(&mut tokens, |_| Ok(ColorAction::GreenAction))?,
+        Some("blue") => parse_action(&mut tokens, parse_blue_action)?,
+        Some("yellow") => parse_action(&mut tokens, parse_yellow_action)?,
+        Some(first_token) => return Err(format!("Unknown input: {}", first_token)),
+    };
+    if let Some(token) = tokens.next() {
+        return Err(format!("Unexpected input after the {} input: {}", string.split_whitespace().next().unwrap(), token));
+    }
+    Ok(input)
+}
+
+/// Empty clauses are skipped, so a trailing semicolon is fine.
+pub fn parse_turn(string: &str) -> Result<Turn, String> {
+    let mut inputs = Vec::new();
+    for (index, clause) in string.split(';').enumerate() {
+        if clause.trim().is_empty() {
+            continue;
+        }
+        let input = parse_input(clause).map_err(|error| format!("Clause {} ({}): {}", index + 1, clause.trim(), error))?;
+        inputs.push(input);
+    }
+    if inputs.is_empty() {
+        return Err("No input".into());
+    }
+    Ok(Turn { inputs })
+}
+
+fn parse_action(tokens: &mut SplitWhitespace, action_parser: fn(&mut SplitWhitespace) -> Result<ColorAction, String>)
+    -> Result<Input, String> {
+    let ship = parse_next_token_as(tokens, parse_piece, "ship")?;
+    let system = parse_next_token_as(tokens, parse_system_id, "system ID")?;
+    let color_action = action_parser(tokens)?;
+    Ok(Input::Action(Action { ship, system, color_action }))
+}
+
+fn parse_blue_action(tokens: &mut SplitWhitespace) -> Result<ColorAction, String> {
+    let new_color = parse_next_token_as(tokens, parse_color, "new color")?;
+    Ok(ColorAction::BlueAction(new_color))
+}
+
+fn parse_red_action(tokens: &mut SplitWhitespace) -> Result<ColorAction, String> {
+    let enemy_ship = parse_next_token_as(tokens, parse_piece, "enemy ship")?;
+    let enemy_player = parse_next_token_as(tokens, parse_u8, "enemy player ID")?;
+    Ok(ColorAction::RedAction(RedActionInput { enemy_player, ship_to_take: enemy_ship }))
+}
+
+fn parse_yellow_action(tokens: &mut SplitWhitespace) -> Result<ColorAction, String> {
+    let first_token = tokens.next();
+    match first_token {
+        None => Err("No yellow action input".into()),
+        Some("new") => {
+            let new_star = parse_next_token_as(tokens, parse_piece, "new star")?;
+            Ok(ColorAction::YellowAction(YellowActionInput::Discover(new_star)))
+        },
+        Some("old") => {
+            let system = parse_next_token_as(tokens, parse_system_id, "system ID")?;
+            Ok(ColorAction::YellowAction(YellowActionInput::Existing(system)))
+        },
+        Some(input) => Err(format!("Unknown yellow action {}", input))
+    }
+}
+
+fn parse_next_token_as<T>(tokens: &mut SplitWhitespace, parse: fn(&str) -> Result<T, String>, description: &str) -> Result<T, String> {
+    let piece_input = tokens.next();
+    match piece_input {
+        None => Err(format!("Malformed input, {} not specified", description)),
+        Some(piece_input) => parse(piece_input),
+    }
+}
+
+pub fn parse_u8(string: &str) -> Result<u8, String> {
+    let parse_result = string.parse::<u8>();
+    match parse_result {
+        Err(error) => Err(format!("{} is not a number: {}", string, error)),
+        Ok(system) => Ok(system),
+    }
+}
+
+pub fn parse_system_id(string: &str) -> Result<SystemId, String> {
+    let parse_result = string.parse::<SystemId>();
+    match parse_result {
+        Err(error) => Err(format!("{} is not a system ID: {}", string, error)),
+        Ok(system) => Ok(system),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::rules::RuleSet;
+
+    const SKIRMISH_NOTATION: &str =
+        "turn:0:started bank:2,3,2,2,1,3,2,2,2,2,1,1 out:- next:3 0:sy,mb@0/0:sg,lr/1:sr,my 1:ly,sb@1/1:lg,mb 2:mg/0:ly/1:my";
+
+    #[test]
+    fn input_notation_round_trips() {
+        let mut inputs = Game::new(RuleSet::default()).legal_inputs();
+        inputs.extend(Game::from_notation(SKIRMISH_NOTATION, RuleSet::default()).unwrap().legal_inputs());
+        for &color in ALL_COLORS.iter() {
+            let state = format!("turn:0:sac:{}:3", color_notation(color));
+            let notation = SKIRMISH_NOTATION.replace("turn:0:started", &state);
+            inputs.extend(Game::from_notation(&notation, RuleSet::default()).unwrap().legal_inputs());
+        }
+        for input in inputs {
+            assert_eq!(parse_input(&input_notation(&input)), Ok(input));
+        }
+        assert_eq!(parse_input("end now"), Err("Unexpected input after the end input: now".into()));
+    }
+
+    #[test]
+    fn turns_are_read_clause_by_clause() {
+        let turn = parse_turn("free y 0; yellow lg 0 new lr;; end").unwrap();
+        let inputs: Vec<String> = turn.inputs.iter().map(input_notation).collect();
+        assert_eq!(inputs, vec!["free y 0", "yellow lg 0 new lr", "end"]);
+        assert_eq!(parse_turn("free y 0; yellow lg"), Err("Clause 2 (yellow lg): Malformed input, system ID not specified".into()));
+    }
+}
@@ -0,0 +1,27 @@
+use std::fmt;
+use crate::event::*;
+
+/// Everything that happened as a result of a single input, in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ActionOutcome {
+    pub events: Vec<GameEvent>,
+}
+
+impl ActionOutcome {
+    pub fn new() -> ActionOutcome {
+        ActionOutcome { events: Vec::new() }
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+}
+
+impl fmt::Display for ActionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in self.events.iter() {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+//! Game records: a whole game written out as text so that it can be saved and replayed later.
+//!
+//! A record starts with a header of `Name: value` lines, then a blank line, then every input in
+//! the order it was played, one per line, written the way the CLI takes them:
+//!
+//! ```text
+//! # Homeworlds game record
+//! Players: 2
+//! Date: 2026-10-17
+//! Result: Unfinished
+//! Rules: players=2 stock=3 catastrophe=4 large-starting-ship=no distinct-star-sizes=no mutual-destruction=draw
+//! Position: turn:1:started bank:3,3,3,2,2,3,3,3,2,2,3,1 out:- next:2 0:sy,mb@0/0:ly 1:ly,sb@1/1:lg
+//!
+//! setup sy mb lg
+//! setup ly sb lg
+//! free b 0
+//! blue lg 0 y
+//! end
+//! ```
+//!
+//! Lines starting with `#` are comments, and blank lines among the inputs are ignored. An `undo`
+//! line takes back the input before it, the way a journal records it. Inputs that were undone but
+//! could still be redone are written after the rest, then taken back with as many `undo` lines,
+//! so that loading the record brings them back for redoing. The
+//! `Position` header is the final position in the notation of `Game::to_notation`, which loading
+//! checks the replay against, so that a damaged record isn't silently taken for a different game.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::game::*;
+use crate::notation::*;
+use crate::rules::*;
+
+/// What a record says about the game besides its inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordHeader {
+    pub rules: RuleSet,
+    pub date: Option<String>,
+    pub result: Option<String>,
+    pub position: Option<String>,
+}
+
+/// Where a record couldn't be read or replayed. Lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// How the game has gone so far, as the record's `Result` header puts it.
+pub fn result_description(game: &Game) -> String {
+    match game.state() {
+        State::Finished(winner) => format!("Player {} wins", winner),
+        State::Drawn => "Draw".to_string(),
+        _ => "Unfinished".to_string(),
+    }
+}
+
+pub fn write_record(game: &Game, date: &str) -> String {
+    let mut record = String::from("# Homeworlds game record\n");
+    record.push_str(&format!("Players: {}\n", game.rules().num_players));
+    record.push_str(&format!("Date: {}\n", date));
+    record.push_str(&format!("Result: {}\n", result_description(game)));
+    record.push_str(&format!("Rules: {}\n", game.rules()));
+    record.push_str(&format!("Position: {}\n\n", game.to_notation()));
+    for input in game.inputs() {
+        record.push_str(&input_notation(&input));
+        record.push('\n');
+    }
+    let undone_inputs = game.undone_inputs();
+    for input in undone_inputs.iter() {
+        record.push_str(&input_notation(input));
+        record.push('\n');
+    }
+    for _ in undone_inputs.iter() {
+        record.push_str("undo\n");
+    }
+    record
+}
+
+/// Replays the record from the start. Stops at the first line that can't be read or whose input
+/// is illegal, or if the replayed game doesn't end up with the result or position the header
+/// claims.
+pub fn load_record(record: &str) -> Result<(RecordHeader, Game), RecordError> {
+    load(record, None)
+}
+
+/// Like `load_record`, but also refuses a record of a game played under other rules, e.g. one
+/// that's about to replace a game in progress.
+pub fn load_record_with_rules(record: &str, rules: &RuleSet) -> Result<(RecordHeader, Game), RecordError> {
+    load(record, Some(rules))
+}
+
+fn load(record: &str, expected_rules: Option<&RuleSet>) -> Result<(RecordHeader, Game), RecordError> {
+    let mut lines = record.lines().enumerate().map(|(index, line)| (index + 1, line.trim()));
+    let mut header = RecordHeader { rules: RuleSet::default(), date: None, result: None, position: None };
+    let mut players = None;
+    let mut result_line = 0;
+    let mut rules_line = 1; // Without a Rules header, the whole header implies the default rules
+    let mut position_line = 0;
+    for (line_number, line) in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| RecordError { line: line_number, message };
+        let (name, value) = line.split_once(':')
+            .ok_or_else(|| error(format!("Expected a header line like Name: value, not {}", line)))?;
+        let value = value.trim();
+        match name {
+            "Players" => players = Some((line_number, parse_u8(value).map_err(error)?)),
+            "Date" => header.date = Some(value.to_string()),
+            "Result" => {
+                header.result = Some(value.to_string());
+                result_line = line_number;
+            },
+            "Rules" => {
+                header.rules = value.parse().map_err(error)?;
+                rules_line = line_number;
+            },
+            "Position" => {
+                header.position = Some(value.to_string());
+                position_line = line_number;
+            },
+            _ => return Err(error(format!("Unknown header: {}", name))),
+        }
+    }
+    if let Some((line_number, players)) = players {
+        if players != header.rules.num_players {
+            let message = format!("The record is for {} players, but its rules are for {}", players, header.rules.num_players);
+            return Err(RecordError { line: line_number, message });
+        }
+    }
+    if let Some(rules) = expected_rules {
+        if header.rules != *rules {
+            let message = format!("The game was played under different rules ({})", header.rules);
+            return Err(RecordError { line: rules_line, message });
+        }
+    }
+
+    let mut game = Game::new(header.rules);
+    for (line_number, line) in lines {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| RecordError { line: line_number, message };
+        if line == "undo" {
+            game.undo().ok_or_else(|| error("Nothing to undo".into()))?;
+            continue;
+        }
+        let input = parse_input(line).map_err(error)?;
+        game.apply(input).map_err(|input_error| error(format!("Illegal input {}: {}", line, input_error)))?;
+    }
+    if let Some(result) = &header.result {
+        let replayed_result = result_description(&game);
+        if *result != replayed_result {
+            let message = format!("The record says the result is {}, but replaying it gives {}", result, replayed_result);
+            return Err(RecordError { line: result_line, message });
+        }
+    }
+    if let Some(position) = &header.position {
+        let replayed_position = game.to_notation();
+        if *position != replayed_position {
+            let message = format!("The record says the final position is {}, but replaying it gives {}", position, replayed_position);
+            return Err(RecordError { line: position_line, message });
+        }
+    }
+    Ok((header, game))
+}
+
+/// Today's date in UTC, as YYYY-MM-DD.
+pub fn current_date() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    date_from_days((seconds / 86400) as i64)
+}
+
+// Converts days since 1970-01-01 to a date in the Gregorian calendar. The years are counted from
+// March so that leap days fall at the end; see http://howardhinnant.github.io/date_algorithms.html.
+fn date_from_days(days: i64) -> String {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENING: &str = "\
+# Homeworlds game record
+Players: 2
+Date: 2026-10-17
+Result: Unfinished
+Rules: players=2 stock=3 catastrophe=4 large-starting-ship=no distinct-star-sizes=no mutual-destruction=draw
+Position: turn:1:started bank:3,3,3,2,2,3,3,3,2,2,3,1 out:- next:2 0:sy,mb@0/0:ly 1:ly,sb@1/1:lg
+
+setup sy mb lg
+setup ly sb lg
+free b 0
+blue lg 0 y
+end
+";
+
+    #[test]
+    fn records_replay_and_write_back_the_same() {
+        let (header, game) = load_record(OPENING).unwrap();
+        assert_eq!(header.date.as_deref(), Some("2026-10-17"));
+        assert_eq!(game.inputs().len(), 5);
+        assert_eq!(header.position.as_deref(), Some(game.to_notation().as_str()));
+        assert_eq!(write_record(&game, "2026-10-17"), OPENING);
+    }
+
+    #[test]
+    fn undone_inputs_can_still_be_redone_after_loading() {
+        let (_, mut game) = load_record(OPENING).unwrap();
+        game.undo();
+        game.undo();
+        let (_, mut loaded) = load_record(&write_record(&game, "2026-10-17")).unwrap();
+        assert_eq!(loaded, game);
+        loaded.redo().unwrap();
+        loaded.redo().unwrap();
+        assert_eq!(loaded.to_notation(), load_record(OPENING).unwrap().1.to_notation());
+    }
+
+    #[test]
+    fn loading_reports_the_first_bad_line() {
+        let error = load_record(&OPENING.replace("blue lg 0 y", "blue lg 0 g")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 11: Illegal input blue lg 0 g: The ship is already Green");
+        let error = load_record(&OPENING.replace("free b 0", "free b")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 10: Malformed input, system ID not specified");
+        let error = load_record(&OPENING.replace("Players: 2", "Players: 3")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: The record is for 3 players, but its rules are for 2");
+        let error = load_record(&OPENING.replace("Date", "Venue")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 3: Unknown header: Venue");
+        let error = load_record(&OPENING.replace("Unfinished", "Player 0 wins")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 4: The record says the result is Player 0 wins, but replaying it gives Unfinished");
+        let error = load_record(&OPENING.replace("blue lg 0 y", "blue lg 0 r")).unwrap_err();
+        assert!(error.to_string().starts_with("Line 6: The record says the final position is turn:1:started"), "{}", error);
+    }
+
+    #[test]
+    fn loading_in_place_of_a_game_needs_the_same_rules() {
+        let (_, game) = load_record_with_rules(OPENING, &RuleSet::default()).unwrap();
+        assert_eq!(game.inputs().len(), 5);
+        let rules = RuleSet { stock: 4, ..RuleSet::default() };
+        let error = load_record_with_rules(OPENING, &rules).unwrap_err();
+        assert_eq!(error.to_string(), format!("Line 5: The game was played under different rules ({})", RuleSet::default()));
+        // A damaged record is still refused under the right rules.
+        let error = load_record_with_rules(&OPENING.replace("end\n", ""), &RuleSet::default()).unwrap_err();
+        assert!(error.to_string().starts_with("Line 6: The record says the final position is turn:1:started"), "{}", error);
+    }
+
+    #[test]
+    fn dates_count_from_the_epoch() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(11_016), "2000-02-29");
+        assert_eq!(date_from_days(20_743), "2026-10-17");
+    }
+}
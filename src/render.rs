@@ -0,0 +1,262 @@
+//! Board renderings for terminals, beside the plain text of `Game`'s `Display`.
+//!
+//! The colored rendering draws each piece as a triangle glyph per size step in its own color, e.g.
+//! a medium red is a red `▲▲`, using ANSI escape codes. The current player's ships are in bold and
+//! homeworlds are marked with `⌂`.
+//!
+//! The map lists every system with where its ships could go: the systems adjacent to it, and the
+//! sizes of star that could still be discovered from it.
+//!
+//! The perspective rendering shows the board as the player whose turn it is sees it, calling
+//! players by name: their own home and fleets, the enemy's (the player they may attack), and how
+//! many moves through known systems each system is from the enemy's home.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::collections::btree_map::Entry;
+use crate::common::*;
+use crate::game::*;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+
+fn color_code(color: Color) -> &'static str {
+    match color {
+        Color::RED => "31",
+        Color::GREEN => "32",
+        Color::BLUE => "94", // Plain blue is hard to read on dark backgrounds
+        Color::YELLOW => "33",
+    }
+}
+
+fn glyph(size: Size) -> &'static str {
+    match size {
+        Size::SMALL => "▲",
+        Size::MEDIUM => "▲▲",
+        Size::LARGE => "▲▲▲",
+    }
+}
+
+/// The piece's glyph in its color, in bold if asked.
+pub fn colored_piece(piece: Piece, bold: bool) -> String {
+    let weight = if bold { "1;" } else { "" };
+    format!("\x1b[{}{}m{}{}", weight, color_code(piece.color), glyph(piece.size), RESET)
+}
+
+fn colored_pieces(pieces: &[Piece], bold: bool) -> String {
+    let mut pieces = pieces.to_vec();
+    pieces.sort();
+    pieces.iter().map(|&piece| colored_piece(piece, bold)).collect::<Vec<String>>().join(" ")
+}
+
+/// The same board as `Game`'s `Display`, in color. Ends with a newline.
+pub fn render_colored(game: &Game) -> String {
+    let current_player = game.state().player();
+    let mut rendering = String::from("Bank:");
+    for (index, &color) in ALL_COLORS.iter().enumerate() {
+        if index > 0 {
+            rendering.push_str(" |");
+        }
+        for &size in ALL_SIZES.iter() {
+            let piece = Piece { size, color };
+            rendering.push_str(&format!(" {}×{}", colored_piece(piece, false), game.bank().num_available(piece)));
+        }
+    }
+    rendering.push_str(&format!("\n{}\n", game.state()));
+    for player in game.eliminated() {
+        rendering.push_str(&format!("Player {} has been eliminated\n", player));
+    }
+    rendering.push_str("Systems:\n");
+    for (id, system) in game.systems() {
+        rendering.push_str(&format!("{}: ", id));
+        if let Some(home_player) = system.home_player {
+            rendering.push_str(&format!("⌂ Player {}'s home, ", home_player));
+        }
+        rendering.push_str(&format!("star(s) {}", colored_pieces(&system.stars(), false)));
+        for (&player, ships) in system.ships() {
+            let is_current = current_player == Some(player);
+            let label = if is_current { format!("{}Player {}{}", BOLD, player, RESET) } else { format!("Player {}", player) };
+            rendering.push_str(&format!("; {}: {}", label, colored_pieces(ships, is_current)));
+        }
+        rendering.push('\n');
+    }
+    rendering
+}
+
+/// Every system as in `Game`'s `Display`, each followed by its connections. Ends with a newline.
+pub fn render_map(game: &Game) -> String {
+    let mut rendering = String::new();
+    for (&id, system) in game.systems() {
+        rendering.push_str(&format!("{}: {}\n", id, system));
+        let adjacent_systems = game.adjacent_systems(id);
+        if adjacent_systems.is_empty() {
+            rendering.push_str("   Adjacent to no other system\n");
+        } else {
+            let adjacent_systems: Vec<String> = adjacent_systems.iter().map(|system| system.to_string()).collect();
+            rendering.push_str(&format!("   Adjacent to {}\n", adjacent_systems.join(", ")));
+        }
+        let sizes = game.discoverable_sizes(id);
+        if sizes.is_empty() {
+            rendering.push_str("   Can't discover any new system\n");
+        } else {
+            let sizes: Vec<String> = sizes.iter().map(|size| size.to_string()).collect();
+            rendering.push_str(&format!("   Can discover a {} star\n", sizes.join(" or ")));
+        }
+    }
+    rendering
+}
+
+/// The player's name, or "Player N" if they don't have one.
+pub fn player_name(names: &[String], player: PlayerIndex) -> String {
+    names.get(player as usize).cloned().unwrap_or_else(|| format!("Player {}", player))
+}
+
+/// The board from the point of view of the player whose setup or turn it is. Once the game is
+/// over, nobody's point of view is taken. Ends with a newline.
+pub fn render_perspective(game: &Game, names: &[String]) -> String {
+    let viewer = game.state().player();
+    let enemy = viewer.map(|viewer| game.left_of(viewer));
+    let owner = |player: PlayerIndex, owned: &str| {
+        if Some(player) == viewer {
+            format!("your {}", owned)
+        } else if Some(player) == enemy {
+            format!("enemy {} ({})", owned, player_name(names, player))
+        } else {
+            format!("{}'s {}", player_name(names, player), owned)
+        }
+    };
+    let mut rendering = format!("{}\n", game.bank());
+    rendering.push_str(&match game.state() {
+        State::Setup(player) => format!("Your setup, {}\n", player_name(names, player)),
+        State::Turn(player, phase) => format!("Your turn, {}: {}\n", player_name(names, player), phase),
+        State::Finished(winner) => format!("Game over, {} wins\n", player_name(names, winner)),
+        State::Drawn => "Game over, draw\n".to_string(),
+    });
+    for &player in game.eliminated() {
+        rendering.push_str(&format!("{} has been eliminated\n", player_name(names, player)));
+    }
+    let enemy_home = enemy.and_then(|enemy| game.systems().iter().find(|(_, system)| system.home_player == Some(enemy)));
+    let distances = enemy_home.map(|(&id, _)| distances_from(game, id)).unwrap_or_default();
+    rendering.push_str("Systems:\n");
+    for (&id, system) in game.systems() {
+        let mut stars = system.stars();
+        stars.sort();
+        let stars: Vec<String> = stars.iter().map(|star| star.to_string()).collect();
+        let mut parts = vec![format!("{} {}", stars.join("/"), if stars.len() > 1 { "stars" } else { "star" })];
+        if let Some(home_player) = system.home_player {
+            parts[0] = format!("{}, {}", capitalized(&owner(home_player, "home")), parts[0]);
+        }
+        for (&player, ships) in system.ships() {
+            let mut ships = ships.clone();
+            ships.sort();
+            let ships: Vec<String> = ships.iter().map(|ship| ship.to_string()).collect();
+            parts.push(format!("{}: {}", owner(player, "fleet"), ships.join(", ")));
+        }
+        match (enemy_home, distances.get(&id)) {
+            (Some((&enemy_home_id, _)), _) if enemy_home_id == id => {},
+            (Some(_), Some(1)) => parts.push("1 move from the enemy home".to_string()),
+            (Some(_), Some(distance)) => parts.push(format!("{} moves from the enemy home", distance)),
+            (Some(_), None) => parts.push("no known route to the enemy home".to_string()),
+            (None, _) => {},
+        }
+        rendering.push_str(&format!("{}: {}\n", id, parts.join("; ")));
+    }
+    rendering
+}
+
+fn capitalized(text: &str) -> String {
+    let mut characters = text.chars();
+    match characters.next() {
+        None => String::new(),
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+    }
+}
+
+// How many moves it takes to get from each system to the given one through the systems in play.
+// Systems with no route there are left out.
+fn distances_from(game: &Game, system: SystemId) -> BTreeMap<SystemId, u32> {
+    let mut distances = BTreeMap::new();
+    distances.insert(system, 0);
+    let mut queue = VecDeque::from(vec![system]);
+    while let Some(next) = queue.pop_front() {
+        let distance = distances[&next] + 1;
+        for adjacent_system in game.adjacent_systems(next) {
+            if let Entry::Vacant(entry) = distances.entry(adjacent_system) {
+                entry.insert(distance);
+                queue.push_back(adjacent_system);
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::*;
+    use crate::rules::*;
+
+    #[test]
+    fn pieces_are_drawn_in_their_colors() {
+        assert_eq!(colored_piece(Piece { size: Size::MEDIUM, color: Color::RED }, false), "\x1b[31m▲▲\x1b[0m");
+        assert_eq!(colored_piece(Piece { size: Size::SMALL, color: Color::BLUE }, true), "\x1b[1;94m▲\x1b[0m");
+    }
+
+    #[test]
+    fn the_current_players_ships_stand_out() {
+        let game = Game::from_notation("turn:1:started bank:3,2,3,2,2,3,1,3,1,2,3,2 out:- next:3 0:sy,mb@0/0:lg 1:ly,sb@1/1:lg 2:mr/0:sg/1:sg",
+                                       RuleSet::default()).unwrap();
+        let rendering = render_colored(&game);
+        let small_green = |bold| colored_piece(Piece { size: Size::SMALL, color: Color::GREEN }, bold);
+        assert!(rendering.contains(&format!("2: star(s) \x1b[31m▲▲\x1b[0m; Player 0: {}; \x1b[1mPlayer 1\x1b[0m: {}\n",
+                                            small_green(false), small_green(true))), "{}", rendering);
+        assert!(rendering.contains("0: ⌂ Player 0's home, "));
+        assert!(rendering.starts_with("Bank: \x1b[31m▲\x1b[0m×3 "));
+    }
+
+    #[test]
+    fn the_map_shows_where_ships_can_go() {
+        let mut game = Game::from_notation("turn:0:started bank:3,3,3,2,2,3,2,2,1,2,3,2 out:- next:3 0:sy,mb@0/0:lg 1:ly,sb@1/1:lg 2:mg/0:sg",
+                                           RuleSet::default()).unwrap();
+        assert_eq!(render_map(&game), "\
+0: Player 0's homeworld, binary stars Small Yellow/Medium Blue; Player 0's ship(s): Large Green; 
+   Adjacent to no other system
+   Can discover a Large star
+1: Player 1's homeworld, binary stars Small Blue/Large Yellow; Player 1's ship(s): Large Green; 
+   Adjacent to 2
+   Can discover a Medium star
+2: Medium Green star; Player 0's ship(s): Small Green; 
+   Adjacent to 1
+   Can discover a Small or Large star
+");
+        assert_eq!(game.adjacent_systems(9), vec![]);
+
+        // Every large piece is in play, so there's nothing to discover.
+        game = Game::from_notation("turn:0:started bank:3,2,0,2,2,0,2,3,0,2,3,0 out:- next:2 0:sy,mb@0/0:lr,lr,lr,lb,lb,lb,lg,lg,lg,ly,ly,ly 1:sg,mr@1/1:sb",
+                                   RuleSet::default()).unwrap();
+        assert_eq!(game.discoverable_sizes(0), vec![]);
+    }
+
+    #[test]
+    fn the_perspective_is_the_current_players() {
+        let game = Game::from_notation("turn:0:started bank:2,3,2,2,1,3,2,2,2,2,1,1 out:- next:3 0:sy,mb@0/0:sg,lr/1:sr,my 1:ly,sb@1/1:lg,mb 2:mg/0:ly/1:my",
+                                       RuleSet::default()).unwrap();
+        let names = vec!["Alice".to_string(), "Bob".to_string()];
+        let rendering = render_perspective(&game, &names);
+        assert!(rendering.ends_with("\
+Your turn, Alice: no move selected
+Systems:
+0: Your home, Small Yellow/Medium Blue stars; your fleet: Small Green, Large Red; enemy fleet (Bob): Small Red, Medium Yellow; no known route to the enemy home
+1: Enemy home (Bob), Small Blue/Large Yellow stars; enemy fleet (Bob): Medium Blue, Large Green
+2: Medium Green star; your fleet: Large Yellow; enemy fleet (Bob): Medium Yellow; 1 move from the enemy home
+"), "{}", rendering);
+        assert_eq!(player_name(&names, 2), "Player 2");
+    }
+
+    #[test]
+    fn events_call_players_by_name() {
+        let names = vec!["Alice".to_string(), "Bob".to_string()];
+        let event = GameEvent::ShipCaptured { system: 2, player: 0, enemy_player: 1, ship: Piece { size: Size::MEDIUM, color: Color::YELLOW } };
+        assert_eq!(event.describe(|player| player_name(&names, player)), "Alice captured Bob's Medium Yellow in system 2");
+        assert_eq!(event.to_string(), "Player 0 captured Player 1's Medium Yellow in system 2");
+    }
+}
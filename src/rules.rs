@@ -0,0 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::common::*;
+
+/// Everything about the rules that a group of players might play differently. Saved games record
+/// it, so that replaying them uses the same rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RuleSet {
+    pub num_players: u8,
+    pub stock: u8, // How many of each piece the bank starts with
+    pub catastrophe_count: u8, // Pieces of one color a system needs for a catastrophe
+    pub setup: SetupRules,
+    // House rules
+    pub mutual_destruction: MutualDestruction,
+}
+
+/// Restrictions on the homeworld each player picks at the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SetupRules {
+    pub large_starting_ship: bool,
+    pub distinct_star_sizes: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> RuleSet {
+        RuleSet {
+            num_players: MIN_PLAYERS,
+            stock: 3,
+            catastrophe_count: 4,
+            setup: SetupRules::default(),
+            mutual_destruction: MutualDestruction::Draw,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Sets a single rule by the name it has in the text form.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "players" => self.num_players = parse_number(name, value)?,
+            "stock" => self.stock = parse_number(name, value)?,
+            "catastrophe" => self.catastrophe_count = parse_number(name, value)?,
+            "large-starting-ship" => self.setup.large_starting_ship = parse_flag(name, value)?,
+            "distinct-star-sizes" => self.setup.distinct_star_sizes = parse_flag(name, value)?,
+            "mutual-destruction" => self.mutual_destruction = match value {
+                "draw" => MutualDestruction::Draw,
+                "mover-loses" => MutualDestruction::MoverLoses,
+                _ => return Err(format!("Unknown mutual destruction rule: {}", value)),
+            },
+            _ => return Err(format!("Unknown rule: {}", name)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&self.num_players) {
+            return Err(format!("The game needs {} to {} players", MIN_PLAYERS, MAX_PLAYERS));
+        }
+        if self.stock < 1 {
+            return Err("The bank needs at least 1 of each piece".into());
+        }
+        if self.catastrophe_count < 1 {
+            return Err("A catastrophe needs at least 1 piece".into());
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<u8, String> {
+    value.parse().map_err(|_| format!("Not a valid number for {}: {}", name, value))
+}
+
+fn parse_flag(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(format!("Expected yes or no for {}: {}", name, value)),
+    }
+}
+
+fn flag(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+// Written as space separated name=value pairs, e.g. "players=2 stock=3 catastrophe=4 ...".
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mutual_destruction = match self.mutual_destruction {
+            MutualDestruction::Draw => "draw",
+            MutualDestruction::MoverLoses => "mover-loses",
+        };
+        write!(f, "players={} stock={} catastrophe={} large-starting-ship={} distinct-star-sizes={} mutual-destruction={}",
+               self.num_players, self.stock, self.catastrophe_count,
+               flag(self.setup.large_starting_ship), flag(self.setup.distinct_star_sizes), mutual_destruction)
+    }
+}
+
+// Rules left out keep their defaults.
+impl FromStr for RuleSet {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<RuleSet, String> {
+        let mut rules = RuleSet::default();
+        for pair in string.split_whitespace() {
+            match pair.split_once('=') {
+                None => return Err(format!("Expected name=value: {}", pair)),
+                Some((name, value)) => rules.set(name, value)?,
+            }
+        }
+        rules.validate()?;
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_form_round_trips() {
+        let mut rules = RuleSet::default();
+        assert_eq!(rules.to_string().parse(), Ok(rules));
+        rules.num_players = 4;
+        rules.stock = 5;
+        rules.catastrophe_count = 3;
+        rules.setup = SetupRules { large_starting_ship: true, distinct_star_sizes: true };
+        rules.mutual_destruction = MutualDestruction::MoverLoses;
+        assert_eq!(rules.to_string().parse(), Ok(rules));
+    }
+
+    #[test]
+    fn text_form_rejects_bad_rules() {
+        assert_eq!("".parse(), Ok(RuleSet::default()));
+        assert!("players=5".parse::<RuleSet>().is_err());
+        assert!("stock=0".parse::<RuleSet>().is_err());
+        assert!("stock".parse::<RuleSet>().is_err());
+        assert!("colors=5".parse::<RuleSet>().is_err());
+        assert!("large-starting-ship=maybe".parse::<RuleSet>().is_err());
+    }
+}
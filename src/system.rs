@@ -1,23 +1,29 @@
 use std::fmt;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use crate::common::*;
 use crate::bank::*;
 
-#[derive(Debug)]
+/// A star system: one star, or two for a homeworld, and the ships of every player there.
+#[derive(Debug, Clone, PartialEq)]
 pub struct System {
     star: Piece,
     second_star: Option<Piece>,
     pub home_player: Option<PlayerIndex>,
-    ships: HashMap<PlayerIndex, Vec<Piece>>,
+    ships: BTreeMap<PlayerIndex, Vec<Piece>>,
 }
 
 impl System {
     pub fn new_homeworld(stars: [Piece; 2], player: PlayerIndex) -> System {
+        System::new_binary(stars, Some(player))
+    }
+
+    /// A system with two stars: a homeworld, or what's left of one after its owner was eliminated.
+    pub fn new_binary(stars: [Piece; 2], home_player: Option<PlayerIndex>) -> System {
         System {
             star: stars[0],
             second_star: Some(stars[1]),
-            home_player: Some(player),
-            ships: System::no_ships(),
+            home_player,
+            ships: BTreeMap::new(),
         }
     }
 
@@ -26,16 +32,8 @@ impl System {
             star,
             second_star: None,
             home_player: None,
-            ships: System::no_ships(),
-        }
-    }
-
-    fn no_ships() -> HashMap<PlayerIndex, Vec<Piece>> {
-        let mut initial_ships = HashMap::new();
-        for player_index in 0..NUM_PLAYERS {
-            initial_ships.insert(player_index, Vec::new());
+            ships: BTreeMap::new(),
         }
-        initial_ships
     }
 
     pub fn stars(&self) -> Vec<Piece> {
@@ -50,26 +48,50 @@ impl System {
     }
 
     pub fn add_ship(&mut self, player: PlayerIndex, ship: Piece) {
-        self.ships.get_mut(&player).unwrap().push(ship);
+        self.ships.entry(player).or_default().push(ship);
     }
 
-    pub fn remove_ship(&mut self, player: PlayerIndex, ship: Piece) -> Result<(), InputError> {
-        let player_ships = self.ships.get_mut(&player).unwrap();
+    /// Whether the player had the ship to remove.
+    pub fn remove_ship(&mut self, player: PlayerIndex, ship: Piece) -> bool {
+        let player_ships = match self.ships.get_mut(&player) {
+            None => return false,
+            Some(player_ships) => player_ships,
+        };
         let ship_position = player_ships.iter().position(|player_ship| *player_ship == ship);
         if let Some(ship_position) = ship_position {
             player_ships.remove(ship_position);
-            Ok(())
+            if player_ships.is_empty() {
+                self.ships.remove(&player);
+            }
+            true
         } else {
-            Err(InputError::NoSuchShip)
+            false
         }
     }
 
     pub fn has_ship(&self, player: PlayerIndex, ship: Piece) -> bool {
-        self.ships.get(&player).unwrap().iter().any(|player_ship| ship == *player_ship)
+        self.get_ships(player).contains(&ship)
+    }
+
+    /// Each player's ships, in seat order. Only players with ships in the system are listed.
+    pub fn ships(&self) -> &BTreeMap<PlayerIndex, Vec<Piece>> {
+        &self.ships
     }
 
-    pub fn get_ships(&self, player: PlayerIndex) -> &Vec<Piece> {
-        self.ships.get(&player).unwrap()
+    pub fn get_ships(&self, player: PlayerIndex) -> &[Piece] {
+        self.ships.get(&player).map_or(&[], |ships| ships.as_slice())
+    }
+
+    /// Takes all of the player's ships out of the system, returning them.
+    pub fn remove_all_ships(&mut self, player: PlayerIndex) -> Vec<Piece> {
+        self.ships.remove(&player).unwrap_or_default()
+    }
+
+    /// How many times the piece appears in the system, as a star or as anyone's ship.
+    pub fn count_piece(&self, piece: Piece) -> usize {
+        let stars = self.stars().iter().filter(|&&star| star == piece).count();
+        let ships: usize = self.ships.values().map(|ships| ships.iter().filter(|&&ship| ship == piece).count()).sum();
+        stars + ships
     }
 
     pub fn is_adjacent(&self, other_system: &System) -> bool {
@@ -85,14 +107,14 @@ impl System {
         true
     }
 
-    pub fn color_count(&self, color: Color) -> i32 {
+    pub fn color_count(&self, color: Color) -> u8 {
         let mut count = 0;
         for star in self.stars() {
             if star.color == color {
                 count += 1;
             }
         }
-        for ships in self.ships.values().into_iter() {
+        for ships in self.ships.values() {
             for ship in ships {
                 if ship.color == color {
                     count += 1;
@@ -102,57 +124,60 @@ impl System {
         count
     }
 
-    pub fn catastrophe(&mut self, color: Color, bank: &mut Bank) -> CatastropheResult {
-        let player_has_no_ships: Vec<bool> = self.ships.values_mut().into_iter().map(|ships| {
-            ships.retain(|&ship| {
-                if ship.color != color {
-                    return true;
-                }
-                bank.add(ship);
-                return false;
-            });
-            ships.is_empty()
-        }).collect();
-        let no_ships_left = player_has_no_ships.iter().all(|&empty| empty);
+    pub fn catastrophe(&mut self, color: Color, bank: &mut Bank) -> Result<CatastropheResult, InputError> {
+        let mut destroyed_ships = Vec::new();
+        for (&player, ships) in self.ships.iter_mut() {
+            destroyed_ships.extend(ships.iter().filter(|ship| ship.color == color).map(|&ship| (player, ship)));
+            ships.retain(|ship| ship.color != color);
+        }
+        self.ships.retain(|_, ships| !ships.is_empty());
+        destroyed_ships.sort_by_key(|&(player, _)| player);
+        for &(_, ship) in destroyed_ships.iter() {
+            bank.add(ship)?;
+        }
+        let mut destroyed_stars = Vec::new();
+        let no_ships_left = self.is_empty();
         let mut no_stars_left = false;
         if !no_ships_left {
             let stars = self.stars();
             let stars_to_kill: Vec<Piece> = stars.iter()
                 .filter(|&star| star.color == color)
-                .map(|star| *star).collect();
+                .copied().collect();
             if stars_to_kill.len() >= stars.len() {
                 // Everything in the system will get banked later because of this.
                 no_stars_left = true;
+                destroyed_stars = stars_to_kill;
             } else if !stars_to_kill.is_empty() {
                 // One star dies and the other remains
-                let &star_to_kill = stars_to_kill.get(0).unwrap();
-                let only_star_left = stars.iter().filter(|&&star| star != star_to_kill).next().unwrap();
-                bank.add(star_to_kill);
+                let star_to_kill = stars_to_kill[0];
+                let only_star_left = stars.iter().find(|&&star| star != star_to_kill).unwrap();
+                bank.add(star_to_kill)?;
                 self.star = *only_star_left;
                 self.second_star = None;
+                destroyed_stars.push(star_to_kill);
             }
         }
-        if no_ships_left || no_stars_left {
+        let evaporated = no_ships_left || no_stars_left;
+        if evaporated {
             // Evaporate: bank everything.
             for ships in self.ships.values() {
                 for &ship in ships {
-                    bank.add(ship);
+                    bank.add(ship)?;
                 }
             }
             for star in self.stars() {
-                bank.add(star);
+                bank.add(star)?;
             }
-            CatastropheResult::SystemEvaporated
-        } else {
-            CatastropheResult::SystemStillExists
         }
+        Ok(CatastropheResult { destroyed_ships, destroyed_stars, evaporated })
     }
 }
 
-pub enum CatastropheResult {
-    SystemStillExists,
+pub struct CatastropheResult {
+    pub destroyed_ships: Vec<(PlayerIndex, Piece)>,
+    pub destroyed_stars: Vec<Piece>,
     // The system should not be used after it's evaporated.
-    SystemEvaporated,
+    pub evaporated: bool,
 }
 
 impl fmt::Display for System {
@@ -160,8 +185,10 @@ impl fmt::Display for System {
         if let Some(home_player) = self.home_player {
             write!(f, "Player {}'s homeworld, ", home_player)?;
         }
-        if let Some(second_star) = self.second_star {
-            write!(f, "binary stars {}/{}; ", self.star, second_star)?;
+        let mut stars = self.stars();
+        stars.sort();
+        if let [star, second_star] = stars[..] {
+            write!(f, "binary stars {}/{}; ", star, second_star)?;
         } else {
             write!(f, "{} star; ", self.star)?;
         }
@@ -169,6 +196,8 @@ impl fmt::Display for System {
             if ships.is_empty() {
                 continue;
             }
+            let mut ships = ships.clone();
+            ships.sort();
             write!(f, "Player {}'s ship(s): ", player)?;
             for (index, ship) in ships.iter().enumerate() {
                 write!(f, "{}", ship)?;